@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::time::Instant;
+
+use transactions::accounts::account::Account;
+use transactions::accounts::account_map::AccountHasher;
+use transactions::transactions::ids::ClientId;
+
+const CLIENTS: u32 = 10_000;
+const ROUNDS: u32 = 100;
+
+
+/// Times inserting and looking up accounts in a map built with the given hasher.
+///
+/// # Arguments
+/// * name (&str): the label printed with the timing
+/// * hasher (S): the hasher to build the map with
+fn bench_hasher<S: BuildHasher>(name: &str, hasher: S) {
+    let mut accounts: HashMap<ClientId, Account, S> = HashMap::with_hasher(hasher);
+    let start = Instant::now();
+
+    for client in 0..CLIENTS {
+        accounts.insert(ClientId(client), Account::new(ClientId(client)));
+    }
+    let mut found = 0;
+    for _ in 0..ROUNDS {
+        for client in 0..CLIENTS {
+            if accounts.contains_key(&ClientId(client)) {
+                found += 1;
+            }
+        }
+    }
+    println!("{}: {:?} ({} lookups)", name, start.elapsed(), found);
+}
+
+
+fn main() {
+    bench_hasher("std RandomState", std::collections::hash_map::RandomState::new());
+    bench_hasher("AccountHasher", AccountHasher::default());
+}
@@ -0,0 +1,57 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use smallvec::SmallVec;
+
+use transactions::accounts::account::INLINE_TRANSACTIONS;
+use transactions::transactions::enums::TransactionType::DEPOSIT;
+use transactions::transactions::ids::{ClientId, TxId};
+use transactions::transactions::transaction::Transaction;
+
+const ACCOUNTS: u32 = 10_000;
+const TRANSACTIONS_PER_ACCOUNT: u32 = 3;
+
+
+/// This struct is responsible for counting the allocations made through the system allocator.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        return unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+
+/// Counts the allocations made filling a log per account with a few transactions.
+///
+/// # Arguments
+/// * name (&str): the label printed with the count
+/// * new_log (F): builds an empty log
+fn bench_log<L: Extend<Transaction>, F: Fn() -> L>(name: &str, new_log: F) {
+    let mut logs: Vec<L> = Vec::with_capacity(ACCOUNTS as usize);
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+
+    for client in 0..ACCOUNTS {
+        let mut log = new_log();
+        log.extend((0..TRANSACTIONS_PER_ACCOUNT).map(|tx| Transaction::new(DEPOSIT, ClientId(client), TxId(tx), Some(1.0))));
+        logs.push(log);
+    }
+    println!("{}: {} allocations for {} accounts of {} transactions", name, ALLOCATIONS.load(Ordering::SeqCst) - before, logs.len(), TRANSACTIONS_PER_ACCOUNT);
+}
+
+
+fn main() {
+    bench_log("Vec", Vec::<Transaction>::new);
+    bench_log("SmallVec", SmallVec::<[Transaction; INLINE_TRANSACTIONS]>::new);
+}
@@ -1,28 +1,74 @@
 use core::panic;
+use std::collections::HashMap;
 
-use crate::transactions::enums::TransactionType;
+use crate::transactions::enums::LockReason;
+use crate::transactions::errors::TransactionError;
+use crate::transactions::ids::{ClientId, TxId};
+use crate::transactions::warnings::TransactionWarning;
+use crate::engine::config::{AmountMismatchPolicy, EngineConfig, LargeAmountPolicy, LockedDisputePolicy, LockHoldPolicy, NegativeTotalPolicy, ResolveTarget, WithdrawalDisputeMode};
 
 use super::super::transactions::transaction::Transaction; 
-use super::super::transactions::enums::TransactionType::{CHARGEBACK, DEPOSIT, WITHDRAWAL, DISPUTE, RESOLVE};
+use super::super::transactions::amount::{exceeds_exact_range, FixedPoint, DEFAULT_SCALE};
+use super::store::{TransactionStore, VecStore};
+use super::super::transactions::enums::TransactionType::{CHARGEBACK, DEPOSIT, WITHDRAWAL, DISPUTE, RESOLVE, FREEZE};
+
+
+/// The number of transactions an account log holds inline before it allocates when the smallvec feature is on.
+#[cfg(feature = "smallvec")]
+pub const INLINE_TRANSACTIONS: usize = 4;
+
+/// The log of transactions on an account, with the smallvec feature it is an inline buffer so accounts with few transactions do not allocate.
+#[cfg(feature = "smallvec")]
+pub type TransactionLog = smallvec::SmallVec<[Transaction; INLINE_TRANSACTIONS]>;
+
+/// The log of transactions on an account, with the smallvec feature it is an inline buffer so accounts with few transactions do not allocate.
+#[cfg(not(feature = "smallvec"))]
+pub type TransactionLog = Vec<Transaction>;
 
 
 /// This struct is responsible for housing data around an account and its transactions. 
 /// 
 /// # Attributes 
-/// * id (i32): the ID of the account 
+/// * id (ClientId): the ID of the account 
 /// * amount_available (f32): the amount of funds available in the account 
 /// * amount_held (f32): the amount of funds held for dispute
 /// * total (f32): amount_available + amount_held
 /// * locked (bool): if the account is locked then transactions cannot occur 
-/// * transaction_log (Vec<Transaction>): transactions performed on the account
+/// * lock_reason (Option<LockReason>): why the account was locked, None while it is open
+/// * locked_by_tx (Option<TxId>): the ID of the chargeback or freeze that locked the account, None while it is open
+/// * transaction_log (S): transactions performed on the account, kept in memory by a VecStore unless another store is given
+/// * noted_transactions (Vec<Transaction>): transactions recorded for audit after the lock without affecting the balances
+/// * total_discrepancy (Option<f32>): the negative total removed when a chargeback total was clamped to zero
+/// * held_by_tx (HashMap<TxId, f32>): the open disputes keyed by transaction ID with the amount held for each
+/// * resolved_holds (HashMap<TxId, f32>): funds kept held after their dispute was resolved, keyed by transaction ID
+/// * withdrawal_disputes (HashMap<TxId, WithdrawalDisputeMode>): the open disputes that are on withdrawals with the mode they were opened under
+/// * first_seen_index (usize): the position of the account in the order accounts were first added to a map
+/// * metadata (HashMap<String, String>): tags an embedder keeps with the account, such as a region or tier, never written to the output
+/// * scale (Option<u32>): the decimal places of the account's currency, taken from the first amount parsed for it, None until then
+/// * warnings (Vec<(TxId, TransactionWarning)>): warnings raised by accepted transactions keyed by transaction ID, an AccountMap moves them into its own warning log
+/// * exact_balances (Option<ExactBalances>): the balances in whole units under the decimal backend, the f32 balances follow them, None under the float backend
+/// 
+/// The balance fields are public for now but will become private, new code should read them through the accessors.
 #[derive(Debug, Clone)]
-pub struct Account {
-    pub id: i32,
+pub struct Account<S: TransactionStore = VecStore> {
+    pub id: ClientId,
     pub amount_available: f32,
     pub amount_held: f32,
     pub total: f32,
     pub locked: bool,
-    pub transaction_log: Vec<Transaction>
+    pub lock_reason: Option<LockReason>,
+    pub locked_by_tx: Option<TxId>,
+    pub transaction_log: S,
+    pub noted_transactions: Vec<Transaction>,
+    pub total_discrepancy: Option<f32>,
+    pub held_by_tx: HashMap<TxId, f32>,
+    pub resolved_holds: HashMap<TxId, f32>,
+    pub withdrawal_disputes: HashMap<TxId, WithdrawalDisputeMode>,
+    pub first_seen_index: usize,
+    pub metadata: HashMap<String, String>,
+    pub scale: Option<u32>,
+    pub warnings: Vec<(TxId, TransactionWarning)>,
+    pub exact_balances: Option<ExactBalances>
 }
 
 impl Account {
@@ -30,198 +76,864 @@ impl Account {
     /// The constructor for the Account struct. 
     /// 
     /// # Arguments 
-    /// * id (i32): the ID for the account also known as client for the transaction 
+    /// * id (ClientId): the ID for the account also known as client for the transaction 
     /// 
     /// # Returns 
     /// * (Account): the newly constructed account
-    pub fn new(id: i32) -> Account {
-        let transaction_log: Vec<Transaction> = Vec::new();
+    pub fn new(id: ClientId) -> Account {
+        return Account::with_store(id, VecStore::new())
+    }
+}
+
+impl<S: TransactionStore> Account<S> {
+
+    /// The constructor for an Account that keeps its transactions in a given store. 
+    /// 
+    /// # Arguments 
+    /// * id (ClientId): the ID for the account also known as client for the transaction 
+    /// * transaction_log (S): the empty store the transactions of the account are kept in
+    /// 
+    /// # Returns 
+    /// * (Account<S>): the newly constructed account
+    pub fn with_store(id: ClientId, transaction_log: S) -> Account<S> {
         return Account{
             id, 
             transaction_log, 
+            noted_transactions: Vec::new(),
+            total_discrepancy: None,
+            held_by_tx: HashMap::new(),
+            resolved_holds: HashMap::new(),
+            withdrawal_disputes: HashMap::new(),
+            first_seen_index: 0,
+            metadata: HashMap::new(),
+            scale: None,
+            warnings: Vec::new(),
+            exact_balances: None,
             amount_available: 0.0, 
             amount_held: 0.0,
             total: 0.0,
-            locked: false
+            locked: false,
+            lock_reason: None,
+            locked_by_tx: None
         }
     }
 
-    /// Extracts previous transactions from the log based on the transaction ID and the type of transaction making the call. 
+    /// Tags the account with a piece of metadata, replacing any earlier value under the key. 
+    /// 
+    /// # Arguments 
+    /// * key (&str): the name of the tag
+    /// * value (&str): the value of the tag
+    pub fn set_meta(&mut self, key: &str, value: &str) {
+        self.metadata.insert(key.to_string(), value.to_string());
+    }
+
+    /// Gets a piece of metadata the account has been tagged with. 
     /// 
     /// # Arguments 
-    /// * transactions (&Vec<Transaction>): the transactions to be searched through for extraction
-    /// * tx (&i32): the ID of the transaction being extracted 
-    /// * transaction_type (&TransactionType): the type of transaction making the call 
+    /// * key (&str): the name of the tag
     /// 
     /// # Returns 
-    /// * (Option<&Transaction>) transaction under that ID and type needed if exists
-    fn extract_transaction<'a>(transactions: &'a Vec<Transaction>, tx: &i32, transaction_type: &TransactionType) -> Option<&'a Transaction> {
-        let mut extracted_transaction: Option<&Transaction> = None;
-        let allowed_category: TransactionType;
+    /// * (Option<&str>): the value of the tag, None if the account does not have it
+    pub fn get_meta(&self, key: &str) -> Option<&str> {
+        return self.metadata.get(key).map(String::as_str)
+    }
 
-        match transaction_type {
-            RESOLVE => {
-                allowed_category = DISPUTE;
+    /// Gets the ID of the client that owns the account. 
+    /// 
+    /// # Returns 
+    /// * (ClientId): the client ID
+    pub fn client_id(&self) -> ClientId {
+        return self.id
+    }
+
+    /// Gets the number of decimal places the balances of the account are kept to. 
+    /// 
+    /// # Arguments 
+    /// * config (&EngineConfig): the config holding the default scale
+    /// 
+    /// # Returns 
+    /// * (u32): the scale of the account's currency, or the default scale if no amount has been parsed for it
+    pub fn scale(&self, config: &EngineConfig) -> u32 {
+        return self.scale.unwrap_or(config.scale_for(None))
+    }
+
+    /// Gets the scale of the account's currency when it has its own, the number of decimal places its balances are written with. 
+    /// 
+    /// # Returns 
+    /// * (Option<u32>): the scale, None if the account is at the default scale and amounts are written as they are
+    pub fn currency_scale(&self) -> Option<u32> {
+        return self.scale.filter(|scale| *scale != DEFAULT_SCALE)
+    }
+
+    /// Gets the funds available in the account. 
+    /// 
+    /// # Returns 
+    /// * (f32): the available funds
+    pub fn available(&self) -> f32 {
+        return self.amount_available
+    }
+
+    /// Gets the funds held for dispute in the account. 
+    /// 
+    /// # Returns 
+    /// * (f32): the held funds
+    pub fn held(&self) -> f32 {
+        return self.amount_held
+    }
+
+    /// Gets the total funds in the account. 
+    /// 
+    /// # Returns 
+    /// * (f32): the total funds
+    pub fn total(&self) -> f32 {
+        return self.total
+    }
+
+    /// Gets the balances of the account as whole units of its scale. 
+    /// 
+    /// # Returns 
+    /// * (Option<ExactBalances>): the exact balances under the decimal backend, None under the float backend
+    pub fn exact_balances(&self) -> Option<ExactBalances> {
+        return self.exact_balances
+    }
+
+    /// Checks if the account has been locked. 
+    /// 
+    /// # Returns 
+    /// * (bool): true if no more transactions can go through the account
+    pub fn is_locked(&self) -> bool {
+        return self.locked
+    }
+
+    /// Gets why the account was locked. 
+    /// 
+    /// # Returns 
+    /// * (Option<LockReason>): the reason for the lock, None if the account is open
+    pub fn lock_reason(&self) -> Option<LockReason> {
+        return self.lock_reason
+    }
+
+    /// Gets the transaction that locked the account. 
+    /// 
+    /// # Returns 
+    /// * (Option<TxId>): the ID of the chargeback or freeze, None while the account is open
+    pub fn locked_by_tx(&self) -> Option<TxId> {
+        return self.locked_by_tx
+    }
+
+    /// Gets the position of the account in the order accounts were first added to a map. 
+    /// 
+    /// # Returns 
+    /// * (usize): 0 for the first account seen, 1 for the second and so on
+    pub fn first_seen_index(&self) -> usize {
+        return self.first_seen_index
+    }
+
+    /// Compares the amount a resolve or chargeback carries with the amount held for its dispute. 
+    /// 
+    /// # Arguments 
+    /// * transaction (&Transaction): the resolve or chargeback
+    /// * config (&EngineConfig): the policy applied to a mismatch and the tolerance for float rounding
+    /// 
+    /// # Returns 
+    /// * (Result<(), TransactionError>): AmountMismatch if the amounts disagree under the reject policy
+    fn check_declared_amount(&mut self, transaction: &Transaction, config: &EngineConfig) -> Result<(), TransactionError> {
+        return match self.held_by_tx.get(&transaction.tx).copied() {
+            Some(held) => self.check_against(transaction, held, config.amount_mismatch_policy, config.epsilon),
+            None => Ok(())
+        }
+    }
+
+    /// Compares the amount a row carries with the amount it refers to, a row without an amount always passes and a mismatch under the warn policy is kept in the warnings. 
+    /// 
+    /// # Arguments 
+    /// * transaction (&Transaction): the dispute, resolve or chargeback
+    /// * expected (f32): the amount the row refers to
+    /// * policy (AmountMismatchPolicy): the policy applied to a mismatch
+    /// * epsilon (f32): the tolerance for float rounding
+    /// 
+    /// # Returns 
+    /// * (Result<(), TransactionError>): AmountMismatch if the amounts disagree under the reject policy
+    fn check_against(&mut self, transaction: &Transaction, expected: f32, policy: AmountMismatchPolicy, epsilon: f32) -> Result<(), TransactionError> {
+        let declared = match transaction.amount {
+            Some(declared) => declared,
+            None => {return Ok(())}
+        };
+        if (declared - expected).abs() <= epsilon {
+            return Ok(())
+        }
+        match policy {
+            AmountMismatchPolicy::Ignore => {return Ok(())},
+            AmountMismatchPolicy::Warn => {
+                self.warnings.push((transaction.tx, TransactionWarning::AmountMismatch{declared, expected}));
+                return Ok(())
             },
-            CHARGEBACK => {
-                allowed_category = DISPUTE;
-            }
-            DISPUTE => {
-                allowed_category = DEPOSIT;
+            AmountMismatchPolicy::Reject => {return Err(TransactionError::AmountMismatch)}
+        }
+    }
+
+    /// Checks a deposit or withdrawal amount is in the range f32 holds exactly, counted in units of the scale it was parsed at. 
+    /// 
+    /// # Arguments 
+    /// * transaction (&Transaction): the deposit or withdrawal
+    /// * amount (f32): the amount of the transaction
+    /// * config (&EngineConfig): the policy applied to a large amount
+    /// 
+    /// # Returns 
+    /// * (Result<(), TransactionError>): AmountTooLarge if the amount is out of range under the reject policy
+    fn check_amount_range(&mut self, transaction: &Transaction, amount: f32, config: &EngineConfig) -> Result<(), TransactionError> {
+        let beyond = match transaction.exact_amount {
+            Some(exact) => exact.exceeds_exact_range(),
+            None => exceeds_exact_range(amount, self.scale(config))
+        };
+        if !beyond {
+            return Ok(())
+        }
+        return self.apply_large_amount_policy(transaction, config)
+    }
+
+    /// Checks the balances a deposit leaves are in the range f32 holds exactly at the scale of the account, 
+    /// as amounts that are each in range can still add up beyond it. 
+    /// 
+    /// # Arguments 
+    /// * transaction (&Transaction): the deposit that was applied
+    /// * config (&EngineConfig): the policy applied to a large balance
+    /// 
+    /// # Returns 
+    /// * (Result<(), TransactionError>): AmountTooLarge if a balance is out of range under the reject policy
+    fn check_balance_range(&mut self, transaction: &Transaction, config: &EngineConfig) -> Result<(), TransactionError> {
+        let scale = self.scale(config);
+        if [self.amount_available, self.amount_held, self.total].iter().all(|balance| !exceeds_exact_range(*balance, scale)) {
+            return Ok(())
+        }
+        return self.apply_large_amount_policy(transaction, config)
+    }
+
+    /// Applies the large amount policy to a deposit or withdrawal that is, or leaves a balance, beyond the exact range of f32. 
+    /// 
+    /// # Arguments 
+    /// * transaction (&Transaction): the deposit or withdrawal
+    /// * config (&EngineConfig): the policy applied to a large amount
+    /// 
+    /// # Returns 
+    /// * (Result<(), TransactionError>): AmountTooLarge under the reject policy
+    fn apply_large_amount_policy(&mut self, transaction: &Transaction, config: &EngineConfig) -> Result<(), TransactionError> {
+        match config.large_amount_policy {
+            LargeAmountPolicy::Ignore => {return Ok(())},
+            LargeAmountPolicy::Warn => {
+                // the amount and the balances it leaves are one warning for the transaction
+                let warning = (transaction.tx, TransactionWarning::AmountBeyondExactRange);
+                if !self.warnings.contains(&warning) {
+                    self.warnings.push(warning);
+                }
+                return Ok(())
             },
-            _ => {
-                panic!("deposits and withdraws do not need to extract previous transactions");
+            LargeAmountPolicy::Reject => {return Err(TransactionError::AmountTooLarge)}
+        }
+    }
+
+    /// Moves an amount between the balances, each count being the number of times the amount is added to a balance, negative to take it away. 
+    /// Under the decimal backend the amount is added in whole units and the f32 balances are set from them so float errors do not build up. 
+    /// 
+    /// # Arguments 
+    /// * amount (f32): the amount moved
+    /// * exact_amount (Option<FixedPoint>): the amount as it was parsed, used by the decimal backend in place of amount when it is at the scale of the balances
+    /// * available (i8): the count for the available funds
+    /// * held (i8): the count for the held funds
+    /// * total (i8): the count for the total funds
+    fn move_funds(&mut self, amount: f32, exact_amount: Option<FixedPoint>, available: i8, held: i8, total: i8) {
+        match self.exact_balances.as_mut() {
+            Some(exact) => {
+                let units = exact.units_of(amount, exact_amount);
+                exact.available += available as i64 * units;
+                exact.held += held as i64 * units;
+                exact.total += total as i64 * units;
+                self.follow_exact_balances();
+            },
+            None => {
+                // a count of zero leaves the balance as it is so the float results are the same as moving the funds by hand
+                if available != 0 {
+                    self.amount_available += available as f32 * amount;
+                }
+                if held != 0 {
+                    self.amount_held += held as f32 * amount;
+                }
+                if total != 0 {
+                    self.total += total as f32 * amount;
+                }
             }
         }
+    }
 
-        for logged_transaction in transactions {
-            if &logged_transaction.tx == tx {
+    /// Sets the f32 balances to the closest values to the exact balances, nothing changes under the float backend. 
+    fn follow_exact_balances(&mut self) {
+        if let Some(exact) = self.exact_balances {
+            self.amount_available = FixedPoint{units: exact.available, scale: exact.scale}.to_f32();
+            self.amount_held = FixedPoint{units: exact.held, scale: exact.scale}.to_f32();
+            self.total = FixedPoint{units: exact.total, scale: exact.scale}.to_f32();
+        }
+    }
 
-                if &allowed_category == &logged_transaction.transaction_type {
-                    extracted_transaction = Some(logged_transaction);
-                    break
+    /// Gets the amount as it was parsed of the transaction a hold is for, so the decimal backend can release it without going through f32. 
+    /// 
+    /// # Arguments 
+    /// * tx (TxId): the ID of the disputed transaction
+    /// * held_amount (f32): the amount held for it
+    /// 
+    /// # Returns 
+    /// * (Option<FixedPoint>): the parsed amount, None if the transaction is not logged or the hold no longer matches it
+    fn held_exact_amount(&self, tx: TxId, held_amount: f32) -> Option<FixedPoint> {
+        return self.transaction_log.find_by_tx(tx).filter(|logged| logged.amount == Some(held_amount)).and_then(|logged| logged.exact_amount)
+    }
+
+    /// Closes an open dispute as a resolve does, the funds go where the config sends resolved funds. 
+    /// 
+    /// # Arguments 
+    /// * tx (TxId): the ID of the disputed transaction, nothing happens if it has no open dispute
+    /// * config (&EngineConfig): the config holding where resolved funds go
+    fn resolve_hold(&mut self, tx: TxId, config: &EngineConfig) {
+        let held_amount = match self.held_by_tx.remove(&tx) {
+            Some(held_amount) => held_amount,
+            None => {return}
+        };
+        let held_exact = self.held_exact_amount(tx, held_amount);
+        match self.withdrawal_disputes.remove(&tx) {
+            // the withdrawal stands so the hold opened by its dispute is undone
+            Some(WithdrawalDisputeMode::HoldReturnedFunds) => {
+                self.move_funds(held_amount, held_exact, 0, -1, -1);
+            },
+            Some(WithdrawalDisputeMode::HoldOriginalDebit) => {
+                self.move_funds(held_amount, held_exact, 1, -1, 0);
+            },
+            None => {
+                match config.resolve_target {
+                    ResolveTarget::Available => {
+                        self.move_funds(held_amount, held_exact, 1, -1, 0);
+                    },
+                    ResolveTarget::StayHeld => {
+                        // the dispute is closed but the funds move under a new hold instead of being released
+                        *self.resolved_holds.entry(tx).or_insert(0.0) += held_amount;
+                    }
                 }
             }
         }
-        return extracted_transaction
+    }
+
+    /// Reverses the funds held for an open dispute as a chargeback does, without locking the account. 
+    /// 
+    /// # Arguments 
+    /// * tx (TxId): the ID of the disputed transaction, nothing happens if it has no open dispute
+    fn charge_back_hold(&mut self, tx: TxId) {
+        let held_amount = match self.held_by_tx.remove(&tx) {
+            Some(held_amount) => held_amount,
+            None => {return}
+        };
+        let held_exact = self.held_exact_amount(tx, held_amount);
+        // every mode decreases the held funds by the amount
+        match self.withdrawal_disputes.remove(&tx) {
+            Some(WithdrawalDisputeMode::HoldReturnedFunds) => {
+                self.move_funds(held_amount, held_exact, 1, -1, 0);
+            },
+            Some(WithdrawalDisputeMode::HoldOriginalDebit) => {
+                self.move_funds(held_amount, held_exact, 2, -1, 1);
+            },
+            None => {
+                self.move_funds(held_amount, held_exact, 0, -1, -1);
+            }
+        }
+    }
+
+    /// Adds a transaction to the account under the default engine config. 
+    /// 
+    /// # Arguments 
+    /// * transaction (Transaction): the transaction to be added to the account 
+    /// 
+    /// # Returns 
+    /// * (Result<Self, TransactionError>): a new updated account if successful, or an error if the rules for the transaction type has been breached
+    pub fn add_transaction(self, transaction: Transaction) -> Result<Self, TransactionError> {
+        return self.add_transaction_with_config(transaction, &EngineConfig::default())
     }
 
     /// Adds a transaction to the account with different rules applying depending on the type of transaction. 
     /// 
     /// # Arguments 
     /// * transaction (Transaction): the transaction to be added to the account 
+    /// * config (&EngineConfig): the policies to apply to the transaction
     /// 
     /// # Returns 
-    /// * (Result<Self, &'static str>): a new updated account if successful, or an error if the rules for the transaction type has been breached
-    pub fn add_transaction(mut self, transaction: Transaction) -> Result<Self, &'static str> {
+    /// * (Result<Self, TransactionError>): a new updated account if successful, or an error if the rules for the transaction type has been breached
+    pub fn add_transaction_with_config(mut self, transaction: Transaction, config: &EngineConfig) -> Result<Self, TransactionError> {
 
         if transaction.client != self.id {
             panic!("transaction id: {} is not the same as account ID: {}", transaction.client, self.id);
         }
 
-        if self.locked == true {
-            return Err("account is locked")
+        if self.locked && transaction.transaction_type == FREEZE {
+            return Err(TransactionError::AlreadyLocked)
+        }
+
+        if self.locked && !(transaction.transaction_type == DEPOSIT && config.locked_accepts_deposits) {
+            // a dispute on a transaction from before the lock can be kept for audit without moving funds
+            if transaction.transaction_type == DISPUTE 
+                && config.locked_dispute_policy == LockedDisputePolicy::Note 
+                && self.transaction_log.find_by_tx(transaction.tx).is_some_and(|logged| logged.transaction_type == DEPOSIT) {
+                self.noted_transactions.push(transaction);
+                return Ok(self)
+            }
+            return Err(TransactionError::AccountLocked)
+        }
+        // the currency of an account is set by its first amount, disputes carry no amount of their own
+        if self.scale.is_none() {
+            self.scale = transaction.exact_amount.map(|exact| exact.scale);
+        }
+        // an account that was not kept by the decimal backend before, such as one read back from output, starts from its f32 balances
+        if config.arithmetic.is_exact() && self.exact_balances.is_none() {
+            self.exact_balances = Some(ExactBalances::from_balances(self.amount_available, self.amount_held, self.total, self.scale(config)));
+        }
+        // without a log there is nothing for a dispute to refer back to
+        if config.no_log && matches!(transaction.transaction_type, DISPUTE | RESOLVE | CHARGEBACK) {
+            return Err(TransactionError::LogDisabled)
+        }
+        // a dispute could refer to a deposit that has been merged into another
+        if config.coalesce_deposits && matches!(transaction.transaction_type, DISPUTE | RESOLVE | CHARGEBACK) {
+            return Err(TransactionError::DisputesDisabled)
         }
         let transaction_reference = &transaction.tx.clone(); // the reference is taken here if needed for disputes
 
+        if transaction.transaction_type == RESOLVE || transaction.transaction_type == CHARGEBACK {
+            self.check_declared_amount(&transaction, config)?;
+        }
+
         match transaction.transaction_type {
             CHARGEBACK => {
-                // extract a dispute => return an error if not
-                let dispute = Account::extract_transaction(&self.transaction_log, 
-                                                                                      transaction_reference, 
-                                                                                      &transaction.transaction_type);
-                let disputed_transaction: &Transaction;
-                match dispute {
+                // only what is held for this transaction is reversed, the other open disputes follow the lock hold policy
+                let held_amount = match self.held_by_tx.get(transaction_reference) {
+                    Some(held_amount) => *held_amount,
                     None => {
-                        return Err("no dispute found for the chargeback");
-                    },
-                    Some(dispute_transaction) => {
-                        // directly unwrap because the dispute would not have been logged if the transaction being disputed didn't exist
-                        disputed_transaction = Account::extract_transaction(&self.transaction_log, 
-                                                                                                    transaction_reference, 
-                                                                                                    &dispute_transaction.transaction_type).unwrap();
-                        
+                        return Err(TransactionError::DisputeNotFound);
                     }
-                }
+                };
                 // check the held funds are there => return an error if not 
-                if self.amount_held < disputed_transaction.amount.unwrap() {
-                    return Err("not enough held funds for the chargeback")
+                if self.amount_held < held_amount {
+                    return Err(TransactionError::InsufficientHeldFunds)
+                }
+                self.charge_back_hold(*transaction_reference);
+                // the other open disputes are sorted so the balances do not depend on the order of the map
+                let mut other_disputes: Vec<TxId> = self.held_by_tx.keys().copied().collect();
+                other_disputes.sort();
+                for tx in other_disputes {
+                    match config.lock_hold_policy {
+                        LockHoldPolicy::LeaveHeld => {},
+                        LockHoldPolicy::Resolve => {self.resolve_hold(tx, config)},
+                        LockHoldPolicy::Chargeback => {self.charge_back_hold(tx)}
+                    }
+                }
+                if self.total < 0.0 && config.negative_total_policy == NegativeTotalPolicy::Clamp {
+                    self.total_discrepancy = Some(self.total);
+                    self.total = 0.0;
+                    if let Some(exact) = self.exact_balances.as_mut() {
+                        exact.total = 0;
+                    }
                 }
-                // decrease the funds by the amount
-                self.amount_held -= disputed_transaction.amount.unwrap();
-                self.total -= disputed_transaction.amount.unwrap();
                 // freeze the acount
                 self.locked = true;
+                self.lock_reason = Some(LockReason::Chargeback);
+                self.locked_by_tx = Some(transaction.tx);
+            },
+            FREEZE => {
+                self.locked = true;
+                self.lock_reason = Some(LockReason::Administrative);
+                self.locked_by_tx = Some(transaction.tx);
             },
             DEPOSIT => {
-                self.amount_available += transaction.amount.unwrap();
-                self.total += transaction.amount.unwrap();
+                let amount = transaction.require_amount()?;
+                if amount == 0.0 {
+                    return Err(TransactionError::ZeroAmount)
+                }
+                if amount < 0.0 {
+                    return Err(TransactionError::NegativeDeposit)
+                }
+                self.check_amount_range(&transaction, amount, config)?;
+                if let Some(max_balance) = config.max_balance {
+                    if self.total + amount > max_balance {
+                        return Err(TransactionError::BalanceCapExceeded)
+                    }
+                }
+                self.move_funds(amount, transaction.exact_amount, 1, 0, 1);
+                self.check_balance_range(&transaction, config)?;
             },
             WITHDRAWAL => {
-                if transaction.amount.unwrap() > self.amount_available {
-                    return Err("not enough funds for withdrawal")
+                let amount = transaction.require_amount()?;
+                if amount == 0.0 {
+                    return Err(TransactionError::ZeroAmount)
+                }
+                self.check_amount_range(&transaction, amount, config)?;
+                // until balances are decimal a withdrawal of the whole balance can be a rounding error above it
+                if amount > self.amount_available + config.epsilon {
+                    return Err(TransactionError::InsufficientFunds)
                 }
-                self.amount_available -= transaction.amount.unwrap();
-                self.total -= transaction.amount.unwrap();
+                // a withdrawal just above the balance takes the balance itself
+                let exact_amount = match amount > self.amount_available {
+                    true => {self.exact_balances.map(|exact| FixedPoint{units: exact.available, scale: exact.scale})},
+                    false => {transaction.exact_amount}
+                };
+                let amount = amount.min(self.amount_available);
+                self.move_funds(amount, exact_amount, -1, 0, -1);
             },
             DISPUTE => {
-                let disputed_transaction = Account::extract_transaction(&self.transaction_log, 
-                                                                                                    transaction_reference, 
-                                                                                                    &transaction.transaction_type);
+                if !config.redispute_allowed && self.transaction_log.iter().any(|logged| &logged.tx == transaction_reference && logged.transaction_type == RESOLVE) {
+                    return Err(TransactionError::AlreadyResolved)
+                }
+                if self.held_by_tx.contains_key(transaction_reference) {
+                    return Err(TransactionError::AlreadyDisputed)
+                }
+                let disputed_transaction = self.transaction_log.find_by_tx(*transaction_reference).filter(|logged| logged.transaction_type == DEPOSIT);
 
                 // process the effect of the dispute if the transaction was found
                 match disputed_transaction {
                     Some(inner_transaction) => {
-                           self.amount_available -= inner_transaction.amount.unwrap();
-                           self.amount_held += inner_transaction.amount.unwrap();
+                           if let Some(max_open_disputes) = config.max_open_disputes {
+                               if self.held_by_tx.len() >= max_open_disputes {
+                                   return Err(TransactionError::TooManyOpenDisputes)
+                               }
+                           }
+                           let disputed_amount = inner_transaction.require_amount()?;
+                           let disputed_exact = inner_transaction.exact_amount;
+                           self.check_against(&transaction, disputed_amount, config.dispute_amount_policy, config.epsilon)?;
+                           self.move_funds(disputed_amount, disputed_exact, -1, 1, 0);
+                           self.held_by_tx.insert(*transaction_reference, disputed_amount);
                     },
                     None => {
-                        // do nothing and return the state as it was before the dispute
-                        return Ok(self)
+                        let disputed_withdrawal = self.transaction_log.find_by_tx(*transaction_reference).filter(|logged| logged.transaction_type == WITHDRAWAL);
+                        match (config.withdrawal_dispute_mode, disputed_withdrawal) {
+                            (Some(mode), Some(inner_transaction)) => {
+                                if let Some(max_open_disputes) = config.max_open_disputes {
+                                    if self.held_by_tx.len() >= max_open_disputes {
+                                        return Err(TransactionError::TooManyOpenDisputes)
+                                    }
+                                }
+                                let disputed_amount = inner_transaction.require_amount()?;
+                                let disputed_exact = inner_transaction.exact_amount;
+                                self.check_against(&transaction, disputed_amount, config.dispute_amount_policy, config.epsilon)?;
+                                match mode {
+                                    WithdrawalDisputeMode::HoldReturnedFunds => {
+                                        self.move_funds(disputed_amount, disputed_exact, 0, 1, 1);
+                                    },
+                                    WithdrawalDisputeMode::HoldOriginalDebit => {
+                                        self.move_funds(disputed_amount, disputed_exact, -1, 1, 0);
+                                    }
+                                }
+                                self.held_by_tx.insert(*transaction_reference, disputed_amount);
+                                self.withdrawal_disputes.insert(*transaction_reference, mode);
+                            },
+                            _ => {
+                                // do nothing and return the state as it was before the dispute
+                                return Ok(self)
+                            }
+                        }
                     }
                 }
             },
             RESOLVE => {
-                let logged_dispute = Account::extract_transaction(&self.transaction_log, 
-                                                                                            transaction_reference, 
-                                                                                            &transaction.transaction_type);
-
-                match logged_dispute {
-                    Some(inner_transaction) => {
-                        let disputed_transaction = Account::extract_transaction(&self.transaction_log, 
-                                                                                            transaction_reference, 
-                                                                                            &inner_transaction.transaction_type).unwrap();
-                        self.amount_available += disputed_transaction.amount.unwrap();
-                        self.amount_held -= disputed_transaction.amount.unwrap();
-                    }, 
-                    None => {
-                        return Ok(self)
-                    }
+                if !self.held_by_tx.contains_key(transaction_reference) {
+                    return Ok(self)
                 }
+                self.resolve_hold(*transaction_reference, config);
+            }
+        }
+        if config.recompute_total {
+            self.total = self.amount_available + self.amount_held;
+            if let Some(exact) = self.exact_balances.as_mut() {
+                exact.total = exact.available + exact.held;
+            }
+            self.follow_exact_balances();
+        }
+        if config.round_each_op {
+            self.round_balances(self.scale(config));
+        }
+        if !config.no_log {
+            match self.transaction_log.last_mut() {
+                Some(last) if config.coalesce_deposits && transaction.transaction_type == DEPOSIT && last.transaction_type == DEPOSIT => {
+                    // the entry keeps the ID of the first deposit in the run
+                    last.amount = Some(last.amount.unwrap_or(0.0) + transaction.amount.unwrap_or(0.0));
+                    last.exact_amount = match (last.exact_amount, transaction.exact_amount) {
+                        (Some(first), Some(next)) if first.scale == next.scale => first.units.checked_add(next.units).map(|units| FixedPoint{units, scale: first.scale}),
+                        _ => None
+                    };
+                },
+                _ => {self.transaction_log.push(transaction)}
             }
         }
-        self.transaction_log.push(transaction);
 
         return Ok(self)
     }
 
+    /// Undoes an accepted deposit or withdrawal and removes it from the log so a later transaction can take its place. 
+    /// 
+    /// # Arguments 
+    /// * tx (TxId): the ID of the deposit or withdrawal to undo
+    /// 
+    /// # Returns 
+    /// * (Result<Self, TransactionError>): the account without the transaction, or an error if it is locked, not found, under dispute or has been resolved
+    pub fn reverse_transaction(mut self, tx: TxId) -> Result<Self, TransactionError> {
+        if self.locked {
+            return Err(TransactionError::AccountLocked)
+        }
+        if self.held_by_tx.contains_key(&tx) || self.withdrawal_disputes.contains_key(&tx) {
+            return Err(TransactionError::AlreadyDisputed)
+        }
+        // a resolved dispute may have left funds held or moved, undoing the amount alone would not undo those
+        if self.resolved_holds.contains_key(&tx) || self.transaction_log.iter().any(|logged| logged.tx == tx && logged.transaction_type == RESOLVE) {
+            return Err(TransactionError::AlreadyResolved)
+        }
+        let reversed = self.transaction_log.remove_by_tx(tx).ok_or(TransactionError::TransactionNotFound)?;
+        let amount = reversed.require_amount()?;
+
+        if reversed.transaction_type == DEPOSIT {
+            self.move_funds(amount, reversed.exact_amount, -1, 0, -1);
+        }
+        else {
+            self.move_funds(amount, reversed.exact_amount, 1, 0, 1);
+        }
+        return Ok(self)
+    }
+
+    /// Grows or shrinks the held funds by a rate for modelling interest or decay, this is not part of normal processing. 
+    /// 
+    /// # Arguments 
+    /// * rate (f32): the change applied to every hold, 0.01 adds 1% and -0.01 removes 1%
+    pub fn apply_hold_adjustment(&mut self, rate: f32) {
+        let factor = 1.0 + rate;
+
+        for held_amount in self.held_by_tx.values_mut().chain(self.resolved_holds.values_mut()) {
+            *held_amount *= factor;
+        }
+        self.amount_held *= factor;
+        self.total = self.amount_available + self.amount_held;
+        // the rate is not a whole number of units so the exact balances are counted again from the adjusted ones
+        if let Some(exact) = self.exact_balances.as_mut() {
+            *exact = ExactBalances::from_balances(self.amount_available, self.amount_held, self.total, exact.scale);
+        }
+    }
+
+    /// Rounds the balances to a number of decimal places so sums of floats compare exactly against the amounts that were parsed. 
+    /// 
+    /// # Arguments 
+    /// * scale (u32): the number of decimal places kept
+    fn round_balances(&mut self, scale: u32) {
+        // the rounding is done in f64 so large balances do not lose digits while scaled up
+        let factor = 10f64.powi(scale as i32);
+        let round = |value: f32| ((value as f64 * factor).round() / factor) as f32;
+        self.amount_available = round(self.amount_available);
+        self.amount_held = round(self.amount_held);
+        self.total = round(self.total);
+    }
+
+    /// Copies the balances of the account without its transaction logs so they can be reported cheaply. 
+    /// 
+    /// # Returns 
+    /// * (AccountSnapshot): the current balances and lock state
+    pub fn snapshot(&self) -> AccountSnapshot {
+        return AccountSnapshot{client: self.id, available: self.amount_available, held: self.amount_held, total: self.total, locked: self.locked, locked_by_tx: self.locked_by_tx}
+    }
+
+    /// Works out the balances the account would have after a transaction with the default config, leaving the account untouched. 
+    /// 
+    /// # Arguments 
+    /// * transaction (&Transaction): the hypothetical transaction
+    /// 
+    /// # Returns 
+    /// * (Result<AccountSnapshot, TransactionError>): the balances after the transaction, or the reason it would be rejected
+    pub fn preview(&self, transaction: &Transaction) -> Result<AccountSnapshot, TransactionError> where S: Clone {
+        return self.preview_with_config(transaction, &EngineConfig::default())
+    }
+
+    /// Works out the balances the account would have after a transaction under a config, leaving the account untouched. 
+    /// 
+    /// # Arguments 
+    /// * transaction (&Transaction): the hypothetical transaction
+    /// * config (&EngineConfig): the policies the transaction would be applied under
+    /// 
+    /// # Returns 
+    /// * (Result<AccountSnapshot, TransactionError>): the balances after the transaction, or the reason it would be rejected
+    pub fn preview_with_config(&self, transaction: &Transaction, config: &EngineConfig) -> Result<AccountSnapshot, TransactionError> where S: Clone {
+        // the transaction is applied to a copy so neither the balances nor the log of this account change
+        let previewed = self.clone().add_transaction_with_config(transaction.clone(), config)?;
+        return Ok(previewed.snapshot())
+    }
+
+    /// Sums the deposits and withdrawals in the transaction log ignoring disputes, resolves and chargebacks. 
+    /// 
+    /// # Returns 
+    /// * (NetFlow): the total deposited, the total withdrawn and the difference
+    pub fn net_flow(&self) -> NetFlow {
+        let mut deposits: f32 = 0.0;
+        let mut withdrawals: f32 = 0.0;
+
+        for logged_transaction in self.transaction_log.iter() {
+            match logged_transaction.transaction_type {
+                DEPOSIT => {deposits += logged_transaction.amount.unwrap_or(0.0)},
+                WITHDRAWAL => {withdrawals += logged_transaction.amount.unwrap_or(0.0)},
+                _ => {}
+            }
+        }
+        return NetFlow{deposits, withdrawals, net: deposits - withdrawals}
+    }
+}
+
+
+/// This struct is responsible for holding the balances of an account at a point in time without its logs. 
+/// 
+/// # Attributes 
+/// * client (ClientId): the ID of the client that owns the account
+/// * available (f32): the funds available
+/// * held (f32): the funds held for dispute
+/// * total (f32): the total funds
+/// * locked (bool): if the account was locked
+/// * locked_by_tx (Option<TxId>): the transaction that locked the account, None if it is open
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AccountSnapshot {
+    pub client: ClientId,
+    pub available: f32,
+    pub held: f32,
+    pub total: f32,
+    pub locked: bool,
+    pub locked_by_tx: Option<TxId>
+}
+
+
+/// This struct is responsible for holding the balances of an account as whole units of its scale under the decimal backend. 
+/// 
+/// # Attributes 
+/// * available (i64): the funds available in units
+/// * held (i64): the funds held for dispute in units
+/// * total (i64): the total funds in units
+/// * scale (u32): the number of decimal places a unit is
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ExactBalances {
+    pub available: i64,
+    pub held: i64,
+    pub total: i64,
+    pub scale: u32
+}
+
+impl ExactBalances {
+
+    /// Counts f32 balances in whole units of a scale. 
+    /// 
+    /// # Arguments 
+    /// * available (f32): the funds available
+    /// * held (f32): the funds held for dispute
+    /// * total (f32): the total funds
+    /// * scale (u32): the number of decimal places a unit is
+    /// 
+    /// # Returns 
+    /// * (ExactBalances): the balances rounded to the nearest unit
+    pub fn from_balances(available: f32, held: f32, total: f32, scale: u32) -> ExactBalances {
+        return ExactBalances{
+            available: FixedPoint::from_f32(available, scale).units,
+            held: FixedPoint::from_f32(held, scale).units,
+            total: FixedPoint::from_f32(total, scale).units,
+            scale
+        }
+    }
+
+    /// Counts an amount in units of the balances, the amount as parsed is used when it is at their scale so a large amount is not rounded by f32. 
+    /// 
+    /// # Arguments 
+    /// * amount (f32): the amount
+    /// * exact_amount (Option<FixedPoint>): the amount as it was parsed, None if it was not parsed from text
+    /// 
+    /// # Returns 
+    /// * (i64): the amount in units
+    fn units_of(&self, amount: f32, exact_amount: Option<FixedPoint>) -> i64 {
+        match exact_amount {
+            Some(exact) if exact.scale == self.scale => {return exact.units},
+            _ => {return FixedPoint::from_f32(amount, self.scale).units}
+        }
+    }
+}
+
+
+/// This struct is responsible for summarising the money that has moved in and out of an account. 
+/// 
+/// # Attributes 
+/// * deposits (f32): the sum of the accepted deposits
+/// * withdrawals (f32): the sum of the accepted withdrawals
+/// * net (f32): deposits - withdrawals
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct NetFlow {
+    pub deposits: f32,
+    pub withdrawals: f32,
+    pub net: f32
 }
 
 
 #[cfg(test)]
 mod account_tests {
 
-    use super::Account;
+    use super::{Account, AccountSnapshot, NetFlow};
     use super::Transaction;
-    use super::{CHARGEBACK, DEPOSIT, WITHDRAWAL, DISPUTE, RESOLVE};
+    use super::{AmountMismatchPolicy, EngineConfig, LargeAmountPolicy, LockedDisputePolicy, LockHoldPolicy, NegativeTotalPolicy, ResolveTarget, WithdrawalDisputeMode};
+    use super::{CHARGEBACK, DEPOSIT, WITHDRAWAL, DISPUTE, RESOLVE, FREEZE};
+    use super::LockReason;
+    use super::super::super::data_access_layer::schema::AccountSchema;
+    use super::super::super::engine::config::OutputOptions;
+    use super::{TransactionError, TransactionWarning};
+    use super::{ClientId, FixedPoint, TxId};
 
     #[test]
     #[should_panic]
     fn test_wrong_client_transaction() {
-        let tx_one =   Transaction{transaction_type: DEPOSIT,    client: 2, tx: 1, amount: Some(1.0)};
-        let account_one = Account::new(1);
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(2), TxId(1), Some(1.0));
+        let account_one = Account::new(ClientId(1));
         let _ = account_one.add_transaction(tx_one);
     }
 
+    #[test]
+    fn test_accessors() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(3), TxId(1), Some(6.0));
+        let tx_two =   Transaction::new(DISPUTE,    ClientId(3), TxId(1), None);
+        let tx_three = Transaction::new(DEPOSIT,    ClientId(3), TxId(2), Some(1.0));
+        let tx_four =  Transaction::new(CHARGEBACK, ClientId(3), TxId(1), None);
+        let mut account_one = Account::new(ClientId(3));
+
+        assert_eq!(ClientId(3), account_one.client_id());
+        assert_eq!(false, account_one.is_locked());
+
+        account_one = account_one.add_transaction(tx_one).unwrap();
+        account_one = account_one.add_transaction(tx_two).unwrap();
+        account_one = account_one.add_transaction(tx_three).unwrap();
+
+        assert_eq!(1.0, account_one.available());
+        assert_eq!(6.0, account_one.held());
+        assert_eq!(7.0, account_one.total());
+
+        account_one = account_one.add_transaction(tx_four).unwrap();
+        assert_eq!(true, account_one.is_locked());
+        assert_eq!(0.0, account_one.held());
+        assert_eq!(1.0, account_one.total());
+    }
+
     #[test]
     fn test_transaction_log() {
-        let tx_one =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 1, amount: Some(1.0)};
-        let tx_two =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 2, amount: Some(1.0)};
-        let mut account_one = Account::new(1);
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(1.0));
+        let tx_two =   Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(1.0));
+        let mut account_one = Account::new(ClientId(1));
 
         account_one = account_one.add_transaction(tx_one).unwrap();
         account_one = account_one.add_transaction(tx_two).unwrap();
 
         assert_eq!(2, account_one.transaction_log.len());
-        assert_eq!(1, account_one.transaction_log[0].tx);
-        assert_eq!(2, account_one.transaction_log[1].tx);
+        assert_eq!(TxId(1), account_one.transaction_log[0].tx);
+        assert_eq!(TxId(2), account_one.transaction_log[1].tx);
     }
 
     #[test]
     fn test_deposit() {
-        let tx_one =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 1, amount: Some(4.0)};
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(4.0));
 
-        let mut account_one = Account::new(1);
+        let mut account_one = Account::new(ClientId(1));
 
         account_one = account_one.add_transaction(tx_one).unwrap();
 
@@ -232,8 +944,8 @@ mod account_tests {
 
     #[test]
     fn test_withdrawal() {
-        let tx_one =   Transaction{transaction_type: WITHDRAWAL,    client: 1, tx: 1, amount: Some(2.5)};
-        let mut account_one = Account::new(1);
+        let tx_one =   Transaction::new(WITHDRAWAL,    ClientId(1), TxId(1), Some(2.5));
+        let mut account_one = Account::new(ClientId(1));
 
         account_one.amount_available = 4.0;
         account_one.total = 4.0;
@@ -246,8 +958,8 @@ mod account_tests {
     #[test]
     #[should_panic]
     fn test_overwithdrawal() {
-        let tx_one =   Transaction{transaction_type: WITHDRAWAL,    client: 1, tx: 1, amount: Some(20.0)};
-        let mut account_one = Account::new(1);
+        let tx_one =   Transaction::new(WITHDRAWAL,    ClientId(1), TxId(1), Some(20.0));
+        let mut account_one = Account::new(ClientId(1));
 
         account_one.amount_available = 4.0;
         account_one.total = 4.0;
@@ -256,14 +968,14 @@ mod account_tests {
 
     #[test]
     fn test_normal_dispute() {
-        let tx_one =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 1, amount: Some(5.0)};
-        let tx_two =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 2, amount: Some(10.0)};
-        let tx_three =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 3, amount: Some(5.0)};
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(10.0));
+        let tx_three =   Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(5.0));
 
-        let tx_four =   Transaction{transaction_type: DISPUTE,    client: 1, tx: 4, amount: None};
-        let tx_five =   Transaction{transaction_type: DISPUTE,    client: 1, tx: 2, amount: None};
+        let tx_four =   Transaction::new(DISPUTE,    ClientId(1), TxId(4), None);
+        let tx_five =   Transaction::new(DISPUTE,    ClientId(1), TxId(2), None);
 
-        let mut account_one = Account::new(1);
+        let mut account_one = Account::new(ClientId(1));
 
         account_one = account_one.add_transaction(tx_one).unwrap();
         account_one = account_one.add_transaction(tx_two).unwrap();
@@ -285,20 +997,20 @@ mod account_tests {
 
     #[test]
     fn test_resolve() {
-        let tx_one =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 1, amount: Some(5.0)};
-        let tx_two =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 2, amount: Some(10.0)};
-        let tx_three =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 3, amount: Some(5.0)};
-        let tx_four =   Transaction{transaction_type: DISPUTE,    client: 1, tx: 2, amount: None};
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(10.0));
+        let tx_three =   Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(5.0));
+        let tx_four =   Transaction::new(DISPUTE,    ClientId(1), TxId(2), None);
 
-        let mut account_one = Account::new(1);
+        let mut account_one = Account::new(ClientId(1));
 
         account_one = account_one.add_transaction(tx_one).unwrap();
         account_one = account_one.add_transaction(tx_two).unwrap();
         account_one = account_one.add_transaction(tx_three).unwrap();
         account_one = account_one.add_transaction(tx_four).unwrap();
 
-        let tx_five =   Transaction{transaction_type: RESOLVE,    client: 1, tx: 3, amount: Some(5.0)};
-        let tx_six =   Transaction{transaction_type: RESOLVE,    client: 1, tx: 2, amount: None};
+        let tx_five =   Transaction::new(RESOLVE,    ClientId(1), TxId(3), Some(5.0));
+        let tx_six =   Transaction::new(RESOLVE,    ClientId(1), TxId(2), None);
 
         account_one = account_one.add_transaction(tx_five).unwrap();
         assert_eq!(10.0, account_one.amount_available);
@@ -315,14 +1027,14 @@ mod account_tests {
 
     #[test]
     fn test_chargeback() {
-        let tx_one =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 1, amount: Some(5.0)};
-        let tx_two =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 2, amount: Some(10.0)};
-        let tx_three =   Transaction{transaction_type: DEPOSIT,    client: 1, tx: 3, amount: Some(5.0)};
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(10.0));
+        let tx_three =   Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(5.0));
 
-        let tx_four =   Transaction{transaction_type: DISPUTE,    client: 1, tx: 2, amount: None};
-        let tx_five =   Transaction{transaction_type: CHARGEBACK,    client: 1, tx: 2, amount: None};
+        let tx_four =   Transaction::new(DISPUTE,    ClientId(1), TxId(2), None);
+        let tx_five =   Transaction::new(CHARGEBACK,    ClientId(1), TxId(2), None);
 
-        let mut account_one = Account::new(1);
+        let mut account_one = Account::new(ClientId(1));
 
         account_one = account_one.add_transaction(tx_one).unwrap();
         account_one = account_one.add_transaction(tx_two).unwrap();
@@ -337,5 +1049,677 @@ mod account_tests {
         assert_eq!(10.0, account_one.total);
         assert_eq!(true, account_one.locked);
     }
-}
 
+    #[test]
+    fn test_locked_dispute_policy() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(10.0));
+        let tx_three = Transaction::new(DISPUTE,    ClientId(1), TxId(1), None);
+        let tx_four =  Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None);
+        let tx_five =  Transaction::new(DISPUTE,    ClientId(1), TxId(2), None);
+        let tx_six =   Transaction::new(DISPUTE,    ClientId(1), TxId(9), None);
+
+        let config = EngineConfig::builder().locked_dispute_policy(LockedDisputePolicy::Note).build();
+        let mut account_one = Account::new(ClientId(1));
+
+        account_one = account_one.add_transaction(tx_one).unwrap();
+        account_one = account_one.add_transaction(tx_two).unwrap();
+        account_one = account_one.add_transaction(tx_three).unwrap();
+        account_one = account_one.add_transaction(tx_four).unwrap();
+
+        // the default policy rejects the dispute
+        assert!(account_one.clone().add_transaction(tx_five.clone()).is_err());
+
+        account_one = account_one.add_transaction_with_config(tx_five, &config).unwrap();
+        assert_eq!(10.0, account_one.amount_available);
+        assert_eq!(0.0, account_one.amount_held);
+        assert_eq!(10.0, account_one.total);
+        assert_eq!(true, account_one.locked);
+        assert_eq!(1, account_one.noted_transactions.len());
+        assert_eq!(4, account_one.transaction_log.len());
+
+        // a dispute on a transaction that never happened is still rejected
+        assert!(account_one.add_transaction_with_config(tx_six, &config).is_err());
+    }
+
+    #[test]
+    fn test_missing_amount() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), None);
+        let tx_two =   Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), None);
+        let account_one = Account::new(ClientId(1));
+
+        assert_eq!(Err(TransactionError::MissingAmount(DEPOSIT)), account_one.clone().add_transaction(tx_one).map(|_| ()));
+        assert_eq!(Err(TransactionError::MissingAmount(WITHDRAWAL)), account_one.add_transaction(tx_two).map(|_| ()));
+    }
+
+    #[test]
+    fn test_max_open_disputes() {
+        let config = EngineConfig::builder().max_open_disputes(Some(2)).build();
+        let mut account_one = Account::new(ClientId(1));
+        for tx in 1..=3 {
+            account_one = account_one.add_transaction_with_config(Transaction::new(DEPOSIT, ClientId(1), TxId(tx), Some(1.0)), &config).unwrap();
+        }
+
+        account_one = account_one.add_transaction_with_config(Transaction::new(DISPUTE, ClientId(1), TxId(1), None), &config).unwrap();
+        account_one = account_one.add_transaction_with_config(Transaction::new(DISPUTE, ClientId(1), TxId(2), None), &config).unwrap();
+        let beyond_cap = account_one.clone().add_transaction_with_config(Transaction::new(DISPUTE, ClientId(1), TxId(3), None), &config);
+        assert_eq!(Err(TransactionError::TooManyOpenDisputes), beyond_cap.map(|_| ()));
+        assert_eq!(2.0, account_one.held());
+
+        // resolving a dispute frees a slot under the cap
+        account_one = account_one.add_transaction_with_config(Transaction::new(RESOLVE, ClientId(1), TxId(1), None), &config).unwrap();
+        account_one = account_one.add_transaction_with_config(Transaction::new(DISPUTE, ClientId(1), TxId(3), None), &config).unwrap();
+        assert_eq!(2.0, account_one.held());
+        assert_eq!(1.0, account_one.available());
+    }
+
+    #[test]
+    fn test_zero_amount() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(0.0));
+        let tx_two =   Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(0.0));
+        let account_one = Account::new(ClientId(1));
+
+        assert_eq!(Err(TransactionError::ZeroAmount), account_one.clone().add_transaction(tx_one).map(|_| ()));
+        assert_eq!(Err(TransactionError::ZeroAmount), account_one.add_transaction(tx_two).map(|_| ()));
+    }
+
+    #[test]
+    fn test_negative_deposit() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(10.0));
+        let tx_two =   Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(-5.0));
+        let account_one = Account::new(ClientId(1)).add_transaction(tx_one).unwrap();
+
+        assert_eq!(Err(TransactionError::NegativeDeposit), account_one.clone().add_transaction(tx_two).map(|_| ()));
+        assert_eq!(10.0, account_one.available());
+        assert_eq!(10.0, account_one.total());
+    }
+
+    #[test]
+    fn test_amount_mismatch_policy() {
+        let config = EngineConfig::builder().amount_mismatch_policy(AmountMismatchPolicy::Reject).build();
+        let mut account_one = Account::new(ClientId(1));
+        account_one = account_one.add_transaction_with_config(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(5.0)), &config).unwrap();
+        account_one = account_one.add_transaction_with_config(Transaction::new(DISPUTE, ClientId(1), TxId(1), None), &config).unwrap();
+
+        let mismatched_resolve = Transaction::new(RESOLVE, ClientId(1), TxId(1), Some(4.0));
+        let mismatched_chargeback = Transaction::new(CHARGEBACK, ClientId(1), TxId(1), Some(6.0));
+        assert_eq!(Err(TransactionError::AmountMismatch), account_one.clone().add_transaction_with_config(mismatched_resolve.clone(), &config).map(|_| ()));
+        assert_eq!(Err(TransactionError::AmountMismatch), account_one.clone().add_transaction_with_config(mismatched_chargeback, &config).map(|_| ()));
+
+        // the default policy keeps using the held amount
+        let resolved = account_one.clone().add_transaction(mismatched_resolve).unwrap();
+        assert_eq!(5.0, resolved.available());
+
+        // a warning keeps the held amount and records the mismatch on the account
+        let warn = EngineConfig::builder().amount_mismatch_policy(AmountMismatchPolicy::Warn).build();
+        let warned = account_one.clone().add_transaction_with_config(Transaction::new(RESOLVE, ClientId(1), TxId(1), Some(4.0)), &warn).unwrap();
+        assert_eq!(5.0, warned.available());
+        assert_eq!(vec![(TxId(1), TransactionWarning::AmountMismatch{declared: 4.0, expected: 5.0})], warned.warnings);
+
+        // a matching amount, like the resolve in test_resolve, goes through under the strict policy
+        let resolved = account_one.add_transaction_with_config(Transaction::new(RESOLVE, ClientId(1), TxId(1), Some(5.0)), &config).unwrap();
+        assert_eq!(5.0, resolved.available());
+        assert_eq!(0.0, resolved.held());
+    }
+
+    #[test]
+    fn test_round_each_op() {
+        let deposit = |tx: u32| Transaction::new(DEPOSIT, ClientId(1), TxId(tx), Some(0.3));
+        let withdrawal = Transaction::new(WITHDRAWAL, ClientId(1), TxId(11), Some(3.0));
+        let exact = EngineConfig::builder().epsilon(0.0).build();
+        let rounded = EngineConfig::builder().epsilon(0.0).round_each_op(true).build();
+
+        let mut drifting = Account::new(ClientId(1));
+        let mut rounding = Account::new(ClientId(1));
+        for tx in 1..=10 {
+            drifting = drifting.add_transaction_with_config(deposit(tx), &exact).unwrap();
+            rounding = rounding.add_transaction_with_config(deposit(tx), &rounded).unwrap();
+        }
+        // ten f32 additions of 0.3 fall just short of 3.0
+        assert!(drifting.available() < 3.0);
+        assert_eq!(Err(TransactionError::InsufficientFunds), drifting.add_transaction_with_config(withdrawal.clone(), &exact).map(|_| ()));
+
+        assert_eq!(3.0, rounding.available());
+        let rounding = rounding.add_transaction_with_config(withdrawal, &rounded).unwrap();
+        assert_eq!(0.0, rounding.available());
+        assert_eq!(0.0, rounding.total());
+    }
+
+    #[test]
+    fn test_locked_accepts_deposits() {
+        let frozen = Account::new(ClientId(1))
+            .add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(5.0))).unwrap()
+            .add_transaction(Transaction::new(FREEZE,  ClientId(1), TxId(2), None)).unwrap();
+        let deposit = Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(2.0));
+        let withdrawal = Transaction::new(WITHDRAWAL, ClientId(1), TxId(4), Some(1.0));
+
+        assert_eq!(Err(TransactionError::AccountLocked), frozen.clone().add_transaction(deposit.clone()).map(|_| ()));
+
+        let config = EngineConfig::builder().locked_accepts_deposits(true).build();
+        let topped_up = frozen.add_transaction_with_config(deposit, &config).unwrap();
+        assert_eq!(7.0, topped_up.available());
+        assert_eq!(7.0, topped_up.total());
+        assert!(topped_up.is_locked());
+        assert_eq!(Err(TransactionError::AccountLocked), topped_up.add_transaction_with_config(withdrawal, &config).map(|_| ()));
+    }
+
+    #[test]
+    fn test_recompute_total() {
+        let config = EngineConfig::builder().recompute_total(true).build();
+        let mut account_one = Account::new(ClientId(1));
+        let mut tx = 0;
+
+        for round in 0..200 {
+            let amount = 0.1 + (round % 7) as f32 * 0.3;
+            tx += 1;
+            account_one = account_one.add_transaction_with_config(Transaction::new(DEPOSIT, ClientId(1), TxId(tx), Some(amount)), &config).unwrap();
+            let deposit_tx = tx;
+            tx += 1;
+            account_one = account_one.add_transaction_with_config(Transaction::new(WITHDRAWAL, ClientId(1), TxId(tx), Some(amount / 3.0)), &config).unwrap();
+
+            if round % 3 == 0 {
+                account_one = account_one.add_transaction_with_config(Transaction::new(DISPUTE, ClientId(1), TxId(deposit_tx), None), &config).unwrap();
+            }
+            if round % 6 == 0 {
+                account_one = account_one.add_transaction_with_config(Transaction::new(RESOLVE, ClientId(1), TxId(deposit_tx), None), &config).unwrap();
+            }
+            assert_eq!(account_one.amount_available + account_one.amount_held, account_one.total);
+        }
+    }
+
+    #[test]
+    fn test_freeze() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(FREEZE,     ClientId(1), TxId(2), None);
+        let tx_three = Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(5.0));
+        let mut account_one = Account::new(ClientId(1));
+
+        account_one = account_one.add_transaction(tx_one).unwrap();
+        account_one = account_one.add_transaction(tx_two).unwrap();
+
+        assert_eq!(true, account_one.is_locked());
+        assert_eq!(Some(LockReason::Administrative), account_one.lock_reason());
+        assert_eq!(5.0, account_one.available());
+        assert_eq!(5.0, account_one.total());
+        assert_eq!(Err(TransactionError::AccountLocked), account_one.add_transaction(tx_three).map(|_| ()));
+    }
+
+    #[test]
+    fn test_freeze_already_frozen() {
+        let tx_one =   Transaction::new(FREEZE,     ClientId(1), TxId(1), None);
+        let tx_two =   Transaction::new(FREEZE,     ClientId(1), TxId(2), None);
+        let mut account_one = Account::new(ClientId(1));
+
+        account_one = account_one.add_transaction(tx_one).unwrap();
+        assert_eq!(Err(TransactionError::AlreadyLocked), account_one.add_transaction(tx_two).map(|_| ()));
+    }
+
+    #[test]
+    fn test_chargeback_lock_reason() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(DISPUTE,    ClientId(1), TxId(1), None);
+        let tx_three = Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None);
+        let mut account_one = Account::new(ClientId(1));
+
+        assert_eq!(None, account_one.lock_reason());
+        account_one = account_one.add_transaction(tx_one).unwrap();
+        account_one = account_one.add_transaction(tx_two).unwrap();
+        account_one = account_one.add_transaction(tx_three).unwrap();
+        assert_eq!(Some(LockReason::Chargeback), account_one.lock_reason());
+    }
+
+    #[test]
+    fn test_max_balance() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(6.0));
+        let tx_two =   Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(4.0));
+        let tx_three = Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(0.5));
+        let config = EngineConfig::builder().max_balance(Some(10.0)).build();
+        let mut account_one = Account::new(ClientId(1));
+
+        account_one = account_one.add_transaction_with_config(tx_one, &config).unwrap();
+        account_one = account_one.add_transaction_with_config(tx_two, &config).unwrap();
+        assert_eq!(10.0, account_one.total());
+
+        assert_eq!(Err(TransactionError::BalanceCapExceeded), account_one.clone().add_transaction_with_config(tx_three.clone(), &config).map(|_| ()));
+        // the default is unlimited
+        assert!(account_one.add_transaction(tx_three).is_ok());
+    }
+
+    #[test]
+    fn test_redispute() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(DISPUTE,    ClientId(1), TxId(1), None);
+        let tx_three = Transaction::new(RESOLVE,    ClientId(1), TxId(1), None);
+        let tx_four =  Transaction::new(DISPUTE,    ClientId(1), TxId(1), None);
+        let mut account_one = Account::new(ClientId(1));
+
+        account_one = account_one.add_transaction(tx_one).unwrap();
+        account_one = account_one.add_transaction(tx_two).unwrap();
+        account_one = account_one.add_transaction(tx_three).unwrap();
+
+        let strict = EngineConfig::builder().redispute_allowed(false).build();
+        assert_eq!(Err(TransactionError::AlreadyResolved), account_one.clone().add_transaction_with_config(tx_four.clone(), &strict).map(|_| ()));
+
+        account_one = account_one.add_transaction(tx_four).unwrap();
+        assert_eq!(0.0, account_one.available());
+        assert_eq!(5.0, account_one.held());
+        assert_eq!(5.0, account_one.total());
+    }
+
+    #[test]
+    fn test_negative_total_policy() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(10.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(8.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None)
+        ];
+        let clamp = EngineConfig::builder().negative_total_policy(NegativeTotalPolicy::Clamp).build();
+        let mut allowed = Account::new(ClientId(1));
+        let mut clamped = Account::new(ClientId(1));
+
+        for transaction in transactions {
+            allowed = allowed.add_transaction(transaction.clone()).unwrap();
+            clamped = clamped.add_transaction_with_config(transaction, &clamp).unwrap();
+        }
+
+        assert_eq!(-8.0, allowed.total());
+        assert_eq!(None, allowed.total_discrepancy);
+
+        assert_eq!(0.0, clamped.total());
+        assert_eq!(-8.0, clamped.available());
+        assert_eq!(Some(-8.0), clamped.total_discrepancy);
+        assert_eq!(true, clamped.is_locked());
+    }
+
+    #[test]
+    fn test_net_flow() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(10.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(5.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(4.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(2), None),
+            Transaction::new(RESOLVE,    ClientId(1), TxId(2), None),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(4), Some(1.5)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(5), Some(100.0))
+        ];
+        let mut account_one = Account::new(ClientId(1));
+
+        for transaction in transactions {
+            account_one = match account_one.clone().add_transaction(transaction) {
+                Ok(account) => account,
+                Err(_) => account_one
+            };
+        }
+
+        assert_eq!(NetFlow{deposits: 15.0, withdrawals: 5.5, net: 9.5}, account_one.net_flow());
+    }
+
+    #[test]
+    fn test_partial_chargeback() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(10.0));
+        let tx_three = Transaction::new(DISPUTE,    ClientId(1), TxId(1), None);
+        let tx_four =  Transaction::new(DISPUTE,    ClientId(1), TxId(2), None);
+        let tx_five =  Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None);
+        let mut account_one = Account::new(ClientId(1));
+
+        account_one = account_one.add_transaction(tx_one).unwrap();
+        account_one = account_one.add_transaction(tx_two).unwrap();
+        account_one = account_one.add_transaction(tx_three).unwrap();
+        account_one = account_one.add_transaction(tx_four).unwrap();
+        assert_eq!(15.0, account_one.held());
+
+        account_one = account_one.add_transaction(tx_five).unwrap();
+        assert_eq!(0.0, account_one.available());
+        assert_eq!(10.0, account_one.held());
+        assert_eq!(10.0, account_one.total());
+        assert_eq!(true, account_one.is_locked());
+        assert_eq!(Some(&10.0), account_one.held_by_tx.get(&TxId(2)));
+        assert_eq!(None, account_one.held_by_tx.get(&TxId(1)));
+    }
+
+    #[test]
+    fn test_resolved_dispute_releases_once() {
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(DISPUTE,    ClientId(1), TxId(1), None);
+        let tx_three = Transaction::new(RESOLVE,    ClientId(1), TxId(1), None);
+        let tx_four =  Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None);
+        let mut account_one = Account::new(ClientId(1));
+
+        account_one = account_one.add_transaction(tx_one).unwrap();
+        account_one = account_one.add_transaction(tx_two.clone()).unwrap();
+        assert_eq!(Err(TransactionError::AlreadyDisputed), account_one.clone().add_transaction(tx_two).map(|_| ()));
+
+        account_one = account_one.add_transaction(tx_three.clone()).unwrap();
+        account_one = account_one.add_transaction(tx_three).unwrap();
+        assert_eq!(5.0, account_one.available());
+        assert_eq!(0.0, account_one.held());
+        assert_eq!(Err(TransactionError::DisputeNotFound), account_one.add_transaction(tx_four).map(|_| ()));
+    }
+
+    #[test]
+    fn test_apply_hold_adjustment() {
+        let mut account_one = Account::new(ClientId(1));
+        account_one = account_one.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(100.0))).unwrap();
+        account_one = account_one.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(2), Some(50.0))).unwrap();
+        account_one = account_one.add_transaction(Transaction::new(DISPUTE, ClientId(1), TxId(1), None)).unwrap();
+
+        account_one.apply_hold_adjustment(0.0);
+        assert_eq!(100.0, account_one.held());
+        assert_eq!(150.0, account_one.total());
+
+        account_one.apply_hold_adjustment(0.01);
+        assert_eq!(101.0, account_one.held());
+        assert_eq!(50.0, account_one.available());
+        assert_eq!(151.0, account_one.total());
+        assert_eq!(101.0, account_one.held_by_tx[&TxId(1)]);
+
+        // the chargeback reverses the adjusted hold
+        account_one = account_one.add_transaction(Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None)).unwrap();
+        assert_eq!(0.0, account_one.held());
+        assert_eq!(50.0, account_one.total());
+    }
+
+    #[test]
+    fn test_transaction_log_past_inline_capacity() {
+        let mut account_one = Account::new(ClientId(1));
+        for tx in 1..=10 {
+            account_one = account_one.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(tx), Some(1.0))).unwrap();
+        }
+        account_one = account_one.add_transaction(Transaction::new(DISPUTE, ClientId(1), TxId(2), None)).unwrap();
+        account_one = account_one.add_transaction(Transaction::new(DISPUTE, ClientId(1), TxId(9), None)).unwrap();
+        account_one = account_one.add_transaction(Transaction::new(CHARGEBACK, ClientId(1), TxId(9), None)).unwrap();
+
+        assert_eq!(13, account_one.transaction_log.len());
+        assert_eq!((1..=10).map(TxId).collect::<Vec<TxId>>(), account_one.transaction_log[..10].iter().map(|logged| logged.tx).collect::<Vec<TxId>>());
+        assert_eq!(8.0, account_one.available());
+        assert_eq!(1.0, account_one.held());
+        assert_eq!(9.0, account_one.total());
+        assert!(account_one.is_locked());
+        #[cfg(feature = "smallvec")]
+        assert!(account_one.transaction_log.transactions.spilled());
+    }
+
+    #[test]
+    fn test_withdraw_whole_balance() {
+        let mut account_one = Account::new(ClientId(1));
+        account_one = account_one.add_transaction(Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0))).unwrap();
+        account_one = account_one.add_transaction(Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(5.0))).unwrap();
+        assert_eq!(0.0, account_one.available());
+        assert_eq!(0.0, account_one.total());
+
+        // 1.0 - 0.6 is just below 0.4 as an f32
+        let mut account_two = Account::new(ClientId(2));
+        account_two = account_two.add_transaction(Transaction::new(DEPOSIT,    ClientId(2), TxId(3), Some(1.0))).unwrap();
+        account_two = account_two.add_transaction(Transaction::new(WITHDRAWAL, ClientId(2), TxId(4), Some(0.6))).unwrap();
+        assert!(account_two.available() < 0.4);
+        account_two = account_two.add_transaction(Transaction::new(WITHDRAWAL, ClientId(2), TxId(5), Some(0.4))).unwrap();
+        assert_eq!(0.0, account_two.available());
+        assert_eq!(0.0, account_two.total());
+
+        // a real shortfall is still rejected
+        let overdrawn = account_two.add_transaction(Transaction::new(WITHDRAWAL, ClientId(2), TxId(6), Some(0.0001)));
+        assert_eq!(Err(TransactionError::InsufficientFunds), overdrawn.map(|_| ()));
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(2.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(RESOLVE,    ClientId(1), TxId(1), None)
+        ];
+        let expected = vec![
+            (ResolveTarget::Available, 7.0, 0.0),
+            (ResolveTarget::StayHeld, 2.0, 5.0)
+        ];
+
+        for (resolve_target, available, held) in expected {
+            let config = EngineConfig::builder().resolve_target(resolve_target).build();
+            let mut account_one = Account::new(ClientId(1));
+            for transaction in transactions.clone() {
+                account_one = account_one.add_transaction_with_config(transaction, &config).unwrap();
+            }
+            assert_eq!(available, account_one.available(), "{:?}", resolve_target);
+            assert_eq!(held, account_one.held(), "{:?}", resolve_target);
+            assert_eq!(7.0, account_one.total(), "{:?}", resolve_target);
+            assert!(account_one.held_by_tx.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_dispute_mode() {
+        let setup = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(10.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(4.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(2), None)
+        ];
+        let resolve = Transaction::new(RESOLVE,    ClientId(1), TxId(2), None);
+        let chargeback = Transaction::new(CHARGEBACK, ClientId(1), TxId(2), None);
+        // (mode, balances after the dispute as available, held, total)
+        let expected = vec![
+            (Some(WithdrawalDisputeMode::HoldReturnedFunds), (6.0, 4.0, 10.0)),
+            (Some(WithdrawalDisputeMode::HoldOriginalDebit), (2.0, 4.0, 6.0)),
+            (None, (6.0, 0.0, 6.0))
+        ];
+
+        for (mode, disputed) in expected {
+            let config = EngineConfig::builder().withdrawal_dispute_mode(mode).build();
+            let mut account_one = Account::new(ClientId(1));
+            for transaction in setup.clone() {
+                account_one = account_one.add_transaction_with_config(transaction, &config).unwrap();
+            }
+            assert_eq!(disputed, (account_one.available(), account_one.held(), account_one.total()), "{:?}", mode);
+
+            // a resolve leaves the withdrawal in place
+            let resolved = account_one.clone().add_transaction_with_config(resolve.clone(), &config).unwrap();
+            assert_eq!((6.0, 0.0, 6.0), (resolved.available(), resolved.held(), resolved.total()), "{:?}", mode);
+
+            // a chargeback reverses the withdrawal
+            let charged_back = account_one.add_transaction_with_config(chargeback.clone(), &config);
+            match mode {
+                Some(_) => {
+                    let charged_back = charged_back.unwrap();
+                    assert_eq!((10.0, 0.0, 10.0), (charged_back.available(), charged_back.held(), charged_back.total()), "{:?}", mode);
+                    assert!(charged_back.is_locked());
+                },
+                None => {assert_eq!(Err(TransactionError::DisputeNotFound), charged_back.map(|_| ()))}
+            }
+        }
+    }
+
+    #[test]
+    fn test_coalesce_deposits() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(1.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(2.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(3.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(4), Some(0.5)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(5), Some(4.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(6), Some(1.5))
+        ];
+        let config = EngineConfig::builder().coalesce_deposits(true).build();
+        let mut plain = Account::new(ClientId(1));
+        let mut coalesced = Account::new(ClientId(1));
+        for transaction in transactions {
+            plain = plain.add_transaction(transaction.clone()).unwrap();
+            coalesced = coalesced.add_transaction_with_config(transaction, &config).unwrap();
+        }
+
+        assert_eq!((plain.available(), plain.held(), plain.total()), (coalesced.available(), coalesced.held(), coalesced.total()));
+        assert_eq!(11.0, coalesced.total());
+        assert_eq!(6, plain.transaction_log.len());
+        let log: Vec<(TxId, Option<f32>)> = coalesced.transaction_log.iter().map(|logged| (logged.tx, logged.amount)).collect();
+        assert_eq!(vec![(TxId(1), Some(6.0)), (TxId(4), Some(0.5)), (TxId(5), Some(5.5))], log);
+
+        let dispute = Transaction::new(DISPUTE, ClientId(1), TxId(1), None);
+        assert_eq!(Err(TransactionError::DisputesDisabled), coalesced.add_transaction_with_config(dispute, &config).map(|_| ()));
+    }
+
+    #[test]
+    fn test_large_amount_policy() {
+        // 16777217 is one past the exact integers of f32 and is parsed as 16777216
+        let large = Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(16777217.0));
+        let small = Transaction::new(DEPOSIT, ClientId(1), TxId(2), Some(1000.0));
+
+        let ignored = Account::new(ClientId(1)).add_transaction(large.clone()).unwrap();
+        assert_eq!(16777216.0, ignored.total());
+
+        let warn = EngineConfig::builder().large_amount_policy(LargeAmountPolicy::Warn).build();
+        let warned = Account::new(ClientId(1)).add_transaction_with_config(large.clone(), &warn).unwrap();
+        assert_eq!(vec![(TxId(1), TransactionWarning::AmountBeyondExactRange)], warned.warnings);
+
+        let config = EngineConfig::builder().large_amount_policy(LargeAmountPolicy::Reject).build();
+        assert_eq!(Err(TransactionError::AmountTooLarge), Account::new(ClientId(1)).add_transaction_with_config(large, &config).map(|_| ()));
+        let account = Account::new(ClientId(1)).add_transaction_with_config(small, &config).unwrap();
+
+        // at the default scale of four places the exact range ends around 1677.7216
+        let withdrawal = Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(2000.0));
+        assert_eq!(Err(TransactionError::AmountTooLarge), account.clone().add_transaction_with_config(withdrawal, &config).map(|_| ()));
+
+        // each deposit is in range but the total they leave is not
+        let second = Transaction::new(DEPOSIT, ClientId(1), TxId(4), Some(1000.0));
+        assert_eq!(Err(TransactionError::AmountTooLarge), account.clone().add_transaction_with_config(second.clone(), &config).map(|_| ()));
+        let warned = account.add_transaction_with_config(second, &warn).unwrap();
+        assert_eq!(2000.0, warned.total());
+        assert_eq!(vec![(TxId(4), TransactionWarning::AmountBeyondExactRange)], warned.warnings);
+    }
+
+    #[test]
+    fn test_large_amount_policy_at_currency_scale() {
+        let config = EngineConfig::builder().large_amount_policy(LargeAmountPolicy::Reject).build();
+        // 100000.00 is ten million cents, in range at two places though it would not be at the default four
+        let cents = Transaction{transaction_type: DEPOSIT, client: ClientId(1), tx: TxId(1), amount: Some(100000.0), exact_amount: Some(FixedPoint{units: 10_000_000, scale: 2})};
+        let account = Account::new(ClientId(1)).add_transaction_with_config(cents, &config).unwrap();
+        assert_eq!(100000.0, account.total());
+
+        let beyond = Transaction{transaction_type: DEPOSIT, client: ClientId(1), tx: TxId(2), amount: Some(200000.0), exact_amount: Some(FixedPoint{units: 20_000_000, scale: 2})};
+        assert_eq!(Err(TransactionError::AmountTooLarge), account.add_transaction_with_config(beyond, &config).map(|_| ()));
+    }
+
+    #[test]
+    fn test_dispute_amount_policy() {
+        let deposit = Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(5.0));
+        let matching = Transaction::new(DISPUTE, ClientId(1), TxId(1), Some(5.0));
+        let mismatching = Transaction::new(DISPUTE, ClientId(1), TxId(1), Some(4.0));
+
+        // by default the amount on a dispute is ignored
+        let ignored = Account::new(ClientId(1)).add_transaction(deposit.clone()).unwrap().add_transaction(mismatching.clone()).unwrap();
+        assert_eq!(5.0, ignored.held());
+
+        let config = EngineConfig::builder().dispute_amount_policy(AmountMismatchPolicy::Reject).build();
+        let account = Account::new(ClientId(1)).add_transaction_with_config(deposit, &config).unwrap();
+        assert_eq!(Err(TransactionError::AmountMismatch), account.clone().add_transaction_with_config(mismatching, &config).map(|_| ()));
+        let disputed = account.add_transaction_with_config(matching, &config).unwrap();
+        assert_eq!(5.0, disputed.held());
+        assert_eq!(0.0, disputed.available());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let plain = Account::new(ClientId(1)).add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(2.0))).unwrap();
+        let mut tagged = plain.clone();
+        tagged.set_meta("region", "eu");
+        tagged.set_meta("tier", "gold");
+        tagged.set_meta("tier", "silver");
+
+        assert_eq!(Some("eu"), tagged.get_meta("region"));
+        assert_eq!(Some("silver"), tagged.get_meta("tier"));
+        assert_eq!(None, tagged.get_meta("segment"));
+
+        // the tags stay in memory, the written row is the same as without them
+        let options = OutputOptions::default();
+        assert_eq!(AccountSchema::convert_from_account(&plain).to_record(&options), AccountSchema::convert_from_account(&tagged).to_record(&options));
+    }
+
+    #[test]
+    fn test_lock_hold_policy() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(3.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(2.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(2), None),
+            Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None)
+        ];
+        // (available, held, total) once the chargeback of tx 1 has locked the account with tx 2 still disputed
+        let expected = vec![
+            (LockHoldPolicy::LeaveHeld,  (2.0, 3.0, 5.0)),
+            (LockHoldPolicy::Resolve,    (5.0, 0.0, 5.0)),
+            (LockHoldPolicy::Chargeback, (2.0, 0.0, 2.0))
+        ];
+
+        for (policy, balances) in expected {
+            let config = EngineConfig::builder().lock_hold_policy(policy).build();
+            let mut account = Account::new(ClientId(1));
+            for transaction in transactions.clone() {
+                account = account.add_transaction_with_config(transaction, &config).unwrap();
+            }
+            assert_eq!(balances, (account.available(), account.held(), account.total()), "{:?}", policy);
+            assert!(account.is_locked());
+            assert_eq!(policy == LockHoldPolicy::LeaveHeld, account.held_by_tx.contains_key(&TxId(2)), "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn test_preview() {
+        let mut account = Account::new(ClientId(1));
+        account = account.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(5.0))).unwrap();
+
+        let overdraw = Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(6.0));
+        assert_eq!(Err(TransactionError::InsufficientFunds), account.preview(&overdraw));
+
+        let withdrawal = Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(1.5));
+        assert_eq!(Ok(AccountSnapshot{client: ClientId(1), available: 3.5, held: 0.0, total: 3.5, locked: false, locked_by_tx: None}), account.preview(&withdrawal));
+
+        // the account itself is left as it was
+        assert_eq!(5.0, account.available());
+        assert_eq!(1, account.transaction_log.len());
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_arithmetic_backends() {
+        use super::super::super::engine::config::ArithmeticBackend;
+        use super::ExactBalances;
+
+        let run = |arithmetic: ArithmeticBackend, deposits: &[Transaction]| {
+            let config = EngineConfig::builder().arithmetic(arithmetic).build();
+            let mut account = Account::new(ClientId(1));
+            for deposit in deposits {
+                account = account.add_transaction_with_config(deposit.clone(), &config).unwrap();
+            }
+            return account.add_transaction_with_config(Transaction::new(DISPUTE, ClientId(1), TxId(3), None), &config).unwrap()
+        };
+
+        // ten f32 deposits of 0.1 sum to a little over one
+        let tenths: Vec<Transaction> = (1..=10).map(|tx| Transaction::new(DEPOSIT, ClientId(1), TxId(tx), Some(0.1))).collect();
+        let float = run(ArithmeticBackend::Float, &tenths);
+        assert_ne!(1.0, float.total());
+        assert_ne!(0.9, float.available());
+        assert_eq!(None, float.exact_balances());
+
+        let decimal = run(ArithmeticBackend::Decimal, &tenths);
+        assert_eq!(1.0, decimal.total());
+        assert_eq!(0.9, decimal.available());
+        assert_eq!(0.1, decimal.held());
+        assert_eq!(Some(ExactBalances{available: 9000, held: 1000, total: 10000, scale: 4}), decimal.exact_balances());
+
+        // beyond the exact range of f32 each small deposit onto a large balance is rounded, the decimal units are not
+        let parsed = |tx: u32, raw: &str| {
+            let exact = FixedPoint::parse(raw, 4).unwrap();
+            return Transaction{transaction_type: DEPOSIT, client: ClientId(1), tx: TxId(tx), amount: Some(exact.to_f32()), exact_amount: Some(exact)}
+        };
+        let mut large = vec![parsed(1, "2000")];
+        large.extend((2..=101).map(|tx| parsed(tx, "0.0001")));
+        let expected_total = FixedPoint{units: 20_000_100, scale: 4}.to_f32();
+        let float = run(ArithmeticBackend::Float, &large);
+        assert!((float.total() - expected_total).abs() > 0.001, "{}", float.total());
+
+        let decimal = run(ArithmeticBackend::Decimal, &large);
+        assert_eq!(expected_total, decimal.total());
+        assert_eq!(Some(ExactBalances{available: 20_000_099, held: 1, total: 20_000_100, scale: 4}), decimal.exact_balances());
+    }
+}
@@ -1,19 +1,111 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
 
+use rustc_hash::FxBuildHasher;
+
+use super::super::transactions::amount::FixedPoint;
 use super::super::transactions::transaction::Transaction;
-use super::account::Account;
+use super::super::transactions::enums::TransactionType;
+use super::super::transactions::enums::TransactionType::{CHARGEBACK, DEPOSIT, DISPUTE, RESOLVE, WITHDRAWAL};
+use super::super::transactions::errors::TransactionError;
+use super::super::transactions::ids::{ClientId, TxId};
+use super::super::transactions::warnings::TransactionWarning;
+use super::account::{Account, AccountSnapshot};
+use super::events::{AlertCallback, BalanceEvent, MinBalanceAlert};
+use super::super::engine::config::{DuplicatePolicy, EngineConfig, OutputOptions, TxIdScope};
+use super::super::data_access_layer::schema::{format_amount_at, AccountSchema};
+
+
+/// The hasher used for the accounts, it is seeded the same on every run so iteration and therefore output order is reproducible.
+pub type AccountHasher = FxBuildHasher;
+
+/// The accounts keyed by client ID, change AccountHasher to swap the hasher everywhere.
+pub type Accounts = HashMap<ClientId, Account, AccountHasher>;
+
+
+/// This enum is responsible for defining the order the error log is read in.
+/// 
+/// # Attributes 
+/// * Input: the order the transactions were added in
+/// * ClientThenTx: sorted by client and then by transaction ID for deterministic audit output
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ErrorLogOrder {
+    #[default]
+    Input,
+    ClientThenTx
+}
+
+
+/// This struct is responsible for summarising a retry of the error log.
+/// 
+/// # Attributes 
+/// * retried (usize): the number of transactions taken from the error log
+/// * succeeded (usize): the number that were accepted on the retry
+/// * still_failing (usize): the number that were rejected again and are back in the error log
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RetryReport {
+    pub retried: usize,
+    pub succeeded: usize,
+    pub still_failing: usize
+}
+
+
+/// This enum is responsible for defining the ways an account can break the rules the engine keeps its balances to.
+/// 
+/// # Attributes 
+/// * TotalMismatch: the total is not available + held, allowing for a total clamped to zero by a chargeback
+#[derive(Debug, PartialEq, Clone)]
+pub enum InvariantError {
+    TotalMismatch { client: ClientId, available: f32, held: f32, total: f32 }
+}
+
+impl fmt::Display for InvariantError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::TotalMismatch { client, available, held, total } => {
+                return write!(f, "client {} has total {} but available {} + held {}", client, total, available, held)
+            }
+        }
+    }
+}
 
 
 /// This struct is responsible for managing the accounts that hold the transactions. 
 /// 
 /// # Attributes 
-/// * accounts (HashMap<i32, Account>): holds the accounts that currently have transactions
+/// * accounts (Accounts): holds the accounts that currently have transactions
 /// * total_transaction_log (Vec<Transaction>): a log of all the successful transactions
-/// * total_error_transaction_log (Vec<Transaction>): a log of all the unsuccessful transactions
+/// * total_error_transaction_log (Vec<(Transaction, TransactionError)>): a log of all the unsuccessful transactions and why they failed, always in the order they were added
+/// * total_warning_log (Vec<(Transaction, TransactionWarning)>): the transactions that were accepted or dropped with a warning and what it was, in the order they were added
+/// * event_sink (Option<Sender<BalanceEvent>>): receives an event for every balance change made by an accepted transaction
+/// * min_balance_alert (Option<(f32, AlertCallback)>): the minimum available funds and the callback fired when a transaction takes an account below it
+/// * config (EngineConfig): the policies applied to every transaction
+/// * used_tx_ids (HashMap<TxId, Vec<ClientId>>): the clients that have made an accepted deposit or withdrawal with each transaction ID, left empty when the log is turned off
+/// * accepted_count (usize): the number of accepted transactions, kept even when the log is turned off
+/// * skipped_rows (Vec<(u64, String)>): the line and reason of every input row that could not be parsed and was skipped, in the order they were read
+#[derive(Clone)]
 pub struct AccountMap {
-    pub accounts: HashMap<i32, Account>,
+    pub accounts: Accounts,
     pub total_transaction_log: Vec<Transaction>,
-    pub total_error_transaction_log: Vec<Transaction>
+    pub total_error_transaction_log: Vec<(Transaction, TransactionError)>,
+    pub total_warning_log: Vec<(Transaction, TransactionWarning)>,
+    pub event_sink: Option<Sender<BalanceEvent>>,
+    pub min_balance_alert: Option<(f32, AlertCallback)>,
+    pub config: EngineConfig,
+    pub used_tx_ids: HashMap<TxId, Vec<ClientId>>,
+    pub accepted_count: usize,
+    pub skipped_rows: Vec<(u64, String)>
+}
+
+impl Default for AccountMap {
+
+    fn default() -> Self {
+        return AccountMap::new()
+    }
 }
 
 impl AccountMap {
@@ -23,46 +115,1238 @@ impl AccountMap {
     /// # Returns
     /// * (AccountMap): constructed blank map for accounts
     pub fn new() -> AccountMap {
-        let accounts: HashMap<i32, Account> = HashMap::new();
+        let accounts: Accounts = Accounts::default();
         let total_transaction_log: Vec<Transaction> = Vec::new();
-        let total_error_transaction_log: Vec<Transaction> = Vec::new();
-        return AccountMap{accounts, total_transaction_log, total_error_transaction_log}
+        let total_error_transaction_log: Vec<(Transaction, TransactionError)> = Vec::new();
+        return AccountMap{accounts, total_transaction_log, total_error_transaction_log, total_warning_log: Vec::new(), event_sink: None, min_balance_alert: None, config: EngineConfig::default(), used_tx_ids: HashMap::new(), accepted_count: 0, skipped_rows: Vec::new()}
+    }
+
+    /// Attaches a sink that will receive the balance changes of every accepted transaction.
+    ///
+    /// # Arguments
+    /// * sink (Sender<BalanceEvent>): the sending half of the channel the events are pushed to
+    ///
+    /// # Returns
+    /// * (Self): the map with the sink attached
+    pub fn with_event_sink(mut self, sink: Sender<BalanceEvent>) -> Self {
+        self.event_sink = Some(sink);
+        return self
+    }
+
+    /// Attaches a callback fired once each time a transaction takes the available funds of an account from at or above a minimum to below it.
+    ///
+    /// # Arguments
+    /// * minimum (f32): the lowest available funds that do not raise an alert
+    /// * callback (F): called with the alert, on the thread applying the transaction
+    ///
+    /// # Returns
+    /// * (Self): the map with the alert attached
+    pub fn with_min_balance_alert<F: Fn(&MinBalanceAlert) + Send + Sync + 'static>(mut self, minimum: f32, callback: F) -> Self {
+        self.min_balance_alert = Some((minimum, Arc::new(callback)));
+        return self
+    }
+
+    /// Checks if an event sink or minimum balance alert is attached so the balances before a transaction only have to be kept when read.
+    ///
+    /// # Returns
+    /// * (bool): true if a change made by a transaction is passed on
+    fn is_observed(&self) -> bool {
+        return self.event_sink.is_some() || self.min_balance_alert.is_some()
+    }
+
+    /// Passes the change made by a transaction to the event sink and the minimum balance alert if they are attached.
+    ///
+    /// # Arguments
+    /// * previous_state (&AccountSnapshot): the balances before the transaction
+    /// * account (&AccountSnapshot): the balances after the transaction
+    /// * tx (TxId): the ID of the transaction
+    fn notify(&self, previous_state: &AccountSnapshot, account: &AccountSnapshot, tx: TxId) {
+        if let Some(sink) = &self.event_sink {
+            for event in BalanceEvent::diff(previous_state, account, tx) {
+                // a dropped receiver means nobody is listening which should not stop processing
+                let _ = sink.send(event);
+            }
+        }
+        if let Some((minimum, callback)) = &self.min_balance_alert {
+            if let Some(alert) = MinBalanceAlert::crossing(previous_state, account, tx, *minimum) {
+                callback(&alert);
+            }
+        }
+    }
+
+    /// Sets the policies applied to every transaction.
+    ///
+    /// # Arguments
+    /// * config (EngineConfig): the config to apply
+    ///
+    /// # Returns
+    /// * (Self): the map with the config set
+    pub fn with_config(mut self, config: EngineConfig) -> Self {
+        self.config = config;
+        return self
+    }
+
+    /// Finds the earlier deposit or withdrawal a transaction reuses the ID of within the configured scope. 
+    /// 
+    /// # Arguments 
+    /// * transaction (&Transaction): the transaction about to be applied
+    /// 
+    /// # Returns 
+    /// * (Option<ClientId>): the client that made the earlier transaction, None if the ID has not been used
+    fn duplicate_of(&self, transaction: &Transaction) -> Option<ClientId> {
+        if transaction.transaction_type != DEPOSIT && transaction.transaction_type != WITHDRAWAL {
+            return None
+        }
+        let clients = self.used_tx_ids.get(&transaction.tx)?;
+        match self.config.tx_id_scope {
+            TxIdScope::Global => {return clients.first().copied()},
+            TxIdScope::PerClient => {return clients.iter().find(|client| **client == transaction.client).copied()},
+            TxIdScope::Unchecked => {return None}
+        }
+    }
+
+    /// Works out the account of the client that made the earlier deposit or withdrawal with a transaction ID as it would be without it,
+    /// the map is not changed so the duplicate replacing it can still be rejected. 
+    /// 
+    /// # Arguments 
+    /// * client (ClientId): the client that made the earlier transaction
+    /// * tx (TxId): the reused transaction ID
+    /// 
+    /// # Returns 
+    /// * (Result<Account, TransactionError>): the account without the earlier transaction, or an error if it can not be undone
+    fn reverse_duplicate(&self, client: ClientId, tx: TxId) -> Result<Account, TransactionError> {
+        // the original may have been merged with the deposits around it so it can not be undone on its own
+        if self.config.coalesce_deposits {
+            return Err(TransactionError::TransactionNotFound)
+        }
+        return self.accounts[&client].clone().reverse_transaction(tx)
     }
 
-    /// Adds a transaction to an account creating a new account if it is not currently present. 
+    /// Removes the earlier deposit or withdrawal a duplicate has replaced from the map's record of it. 
+    /// 
+    /// # Arguments 
+    /// * client (ClientId): the client that made the earlier transaction
+    /// * tx (TxId): the reused transaction ID
+    fn forget_replaced(&mut self, client: ClientId, tx: TxId) {
+        if let Some(clients) = self.used_tx_ids.get_mut(&tx) {
+            clients.retain(|used_by| *used_by != client);
+        }
+        let superseded = self.total_transaction_log.iter().position(|logged| {
+            logged.tx == tx && logged.client == client && (logged.transaction_type == DEPOSIT || logged.transaction_type == WITHDRAWAL)
+        });
+        if let Some(position) = superseded {
+            self.total_transaction_log.remove(position);
+        }
+    }
+
+    /// Adds a transaction to the account of its client creating a new account if it is not currently present. 
     /// 
     /// # Arguments 
     /// * transaction (Transaction): the transaction to be added
-    /// * account_id (i32): the ID of the account to have the transaction added to 
     /// 
     /// # Returns 
     /// * (Self): the updated map with the new transaction and account if it was not present before
-    pub fn add_transaction(mut self, transaction: Transaction, account_id: i32) -> Self {
+    pub fn add_transaction(mut self, mut transaction: Transaction) -> Self {
+        let account_id = transaction.client;
+
+        if transaction.transaction_type == DEPOSIT && transaction.amount.is_none() {
+            transaction.amount = self.config.default_deposit_amount;
+            transaction.exact_amount = None;
+        }
+        if self.config.negative_deposit_as_withdrawal && transaction.transaction_type == DEPOSIT {
+            if let Some(amount) = transaction.amount.filter(|amount| *amount < 0.0) {
+                transaction.transaction_type = WITHDRAWAL;
+                transaction.amount = Some(-amount);
+                transaction.exact_amount = transaction.exact_amount.map(|exact| FixedPoint{units: -exact.units, scale: exact.scale});
+            }
+        }
+
+        // the account of the earlier transaction without it, only put in the map if the duplicate replacing it is accepted
+        let mut replaced: Option<(ClientId, Account)> = None;
+        if let Some(original_client) = self.duplicate_of(&transaction) {
+            match self.config.duplicate_policy {
+                DuplicatePolicy::Reject => {
+                    self.total_error_transaction_log.push((transaction, TransactionError::DuplicateTxId));
+                    return self
+                },
+                DuplicatePolicy::FirstWins => {
+                    self.total_warning_log.push((transaction, TransactionWarning::DuplicateDropped));
+                    return self
+                },
+                DuplicatePolicy::LastWins => {
+                    match self.reverse_duplicate(original_client, transaction.tx) {
+                        Ok(reversed) => {replaced = Some((original_client, reversed))},
+                        Err(error) => {
+                            self.total_error_transaction_log.push((transaction, error));
+                            return self
+                        }
+                    }
+                }
+            }
+        }
 
         let mut account: Account;
+        let previous_state: Option<&Account> = self.accounts.get(&account_id);
 
-        match self.accounts.get(&account_id) {
-            Some(found_account) => {
+        match (&replaced, previous_state) {
+            (Some((original_client, reversed)), _) if *original_client == account_id => {
+                account = reversed.clone();
+            },
+            (_, Some(found_account)) => {
                 account = found_account.clone();
             },
-            None => {
+            (_, None) => {
                 account = Account::new(account_id);
+                // accounts are never removed so the count before insertion only grows
+                account.first_seen_index = self.accounts.len();
             }
         }
 
-        let transaction_result = account.add_transaction(transaction.clone());
+        let previous_state: Option<AccountSnapshot> = match self.is_observed() {
+            true => {Some(previous_state.map(|found_account| found_account.snapshot()).unwrap_or_else(|| account.snapshot()))},
+            false => {None}
+        };
+        let transaction_result = account.add_transaction_with_config(transaction.clone(), &self.config);
 
         match transaction_result {
             Ok(new_state) => {
                 account = new_state;
+                if let Some((original_client, reversed)) = replaced {
+                    if original_client != account_id {
+                        if self.is_observed() {
+                            self.notify(&self.accounts[&original_client].snapshot(), &reversed.snapshot(), transaction.tx);
+                        }
+                        self.accounts.insert(original_client, reversed);
+                    }
+                    self.forget_replaced(original_client, transaction.tx);
+                }
+                for (_, warning) in account.warnings.drain(..) {
+                    self.total_warning_log.push((transaction.clone(), warning));
+                }
+                if let Some(previous_state) = previous_state {
+                    self.notify(&previous_state, &account.snapshot(), transaction.tx);
+                }
                 self.accounts.insert(account_id, account);
-                self.total_transaction_log.push(transaction);
+                self.accepted_count += 1;
+                // the IDs would grow with the input the same as the log, so reused IDs are not detected without it
+                if !self.config.no_log {
+                    if transaction.transaction_type == DEPOSIT || transaction.transaction_type == WITHDRAWAL {
+                        self.used_tx_ids.entry(transaction.tx).or_default().push(transaction.client);
+                    }
+                    self.total_transaction_log.push(transaction);
+                }
             },
-            Err(_) => {
-                // println!("{}", message);
-                self.total_error_transaction_log.push(transaction);
+            Err(error) => {
+                self.total_error_transaction_log.push((transaction, error));
             }
         }
         return self
     }
+
+    /// Gets the account of a client by its typed ID. 
+    /// 
+    /// # Arguments 
+    /// * client (ClientId): the ID of the client
+    /// 
+    /// # Returns 
+    /// * (Option<&Account>): the account, None if the client has no account
+    pub fn account(&self, client: ClientId) -> Option<&Account> {
+        return self.accounts.get(&client)
+    }
+
+    /// Writes a CSV row for every client with the number of accepted transactions of each type and the final balances. 
+    /// 
+    /// # Arguments 
+    /// * w (W): where the summaries are written
+    /// 
+    /// # Returns 
+    /// * (Result<(), csv::Error>): an error if a row could not be written
+    pub fn write_client_summaries<W: Write>(&self, w: W) -> Result<(), csv::Error> {
+        let mut wtr = csv::Writer::from_writer(w);
+        let options = OutputOptions::default();
+        wtr.write_record(["client", "deposits", "withdrawals", "disputes", "resolves", "chargebacks", "available", "held", "total", "locked"])?;
+
+        let mut clients: Vec<&ClientId> = self.accounts.keys().collect();
+        clients.sort();
+        for client in clients {
+            let account = &self.accounts[client];
+            let count = |transaction_type: TransactionType| {
+                return account.transaction_log.iter().filter(|logged| logged.transaction_type == transaction_type).count().to_string()
+            };
+            wtr.write_record([
+                client.to_string(),
+                count(DEPOSIT),
+                count(WITHDRAWAL),
+                count(DISPUTE),
+                count(RESOLVE),
+                count(CHARGEBACK),
+                format_amount_at(account.available(), account.currency_scale(), &options),
+                format_amount_at(account.held(), account.currency_scale(), &options),
+                format_amount_at(account.total(), account.currency_scale(), &options),
+                account.is_locked().to_string()
+            ])?;
+        }
+        wtr.flush()?;
+        return Ok(())
+    }
+
+    /// Lists the clients holding funds with no open dispute or kept hold to account for them, which points to a bug in dispute accounting. 
+    /// 
+    /// # Returns 
+    /// * (Vec<ClientId>): the flagged client IDs in ascending order
+    pub fn inconsistent_held(&self) -> Vec<ClientId> {
+        let mut clients: Vec<ClientId> = self.accounts.values().filter(|account| {
+            account.held() != 0.0 && account.held_by_tx.is_empty() && account.resolved_holds.is_empty()
+        }).map(|account| account.client_id()).collect();
+        clients.sort();
+        return clients
+    }
+
+    /// Checks every account keeps total = available + held, within the configured epsilon, so an engine bug is caught before output. 
+    /// 
+    /// # Returns 
+    /// * (Result<(), Vec<InvariantError>>): every violation found sorted by client, not just the first
+    pub fn assert_invariants(&self) -> Result<(), Vec<InvariantError>> {
+        let mut violations: Vec<InvariantError> = Vec::new();
+
+        for account in self.accounts.values() {
+            // a clamped chargeback removed the negative total it records from the total only
+            let expected_total = account.available() + account.held() - account.total_discrepancy.unwrap_or(0.0);
+            if (account.total() - expected_total).abs() > self.config.epsilon {
+                violations.push(InvariantError::TotalMismatch{client: account.client_id(), available: account.available(), held: account.held(), total: account.total()});
+            }
+        }
+        if violations.is_empty() {
+            return Ok(())
+        }
+        violations.sort_by_key(|violation| match violation {InvariantError::TotalMismatch { client, .. } => *client});
+        return Err(violations)
+    }
+
+    /// Lists the clients whose available funds have gone below zero. 
+    /// 
+    /// # Returns 
+    /// * (Vec<ClientId>): the client IDs in ascending order
+    pub fn negative_available(&self) -> Vec<ClientId> {
+        let mut clients: Vec<ClientId> = self.accounts.values().filter(|account| account.available() < 0.0).map(|account| account.client_id()).collect();
+        clients.sort();
+        return clients
+    }
+
+    /// Gets the accounts that have been locked. 
+    /// 
+    /// # Returns 
+    /// * (Vec<&Account>): the locked accounts in ascending client ID order
+    pub fn locked_accounts(&self) -> Vec<&Account> {
+        let mut accounts: Vec<&Account> = self.accounts.values().filter(|account| account.is_locked()).collect();
+        accounts.sort_by_key(|account| account.client_id());
+        return accounts
+    }
+
+    /// Flags clients that deposited, withdrew funds and then charged the deposit back, getting both the withdrawal and the reversal. 
+    /// This is analysis only and does not change any balances. 
+    /// 
+    /// # Returns 
+    /// * (Vec<ClientId>): the flagged client IDs in ascending order
+    pub fn detect_double_spend(&self) -> Vec<ClientId> {
+        let mut clients: Vec<ClientId> = Vec::new();
+
+        for account in self.accounts.values() {
+            let log = &account.transaction_log;
+
+            for (chargeback_index, chargeback) in log.iter().enumerate().filter(|(_, logged)| logged.transaction_type == CHARGEBACK) {
+                let deposit_index = log[..chargeback_index].iter().position(|logged| logged.tx == chargeback.tx && logged.transaction_type == DEPOSIT);
+
+                if let Some(deposit_index) = deposit_index {
+                    let withdrew_between = log[deposit_index..chargeback_index].iter().any(|logged| logged.transaction_type == WITHDRAWAL);
+                    if withdrew_between {
+                        clients.push(account.client_id());
+                        break
+                    }
+                }
+            }
+        }
+        clients.sort();
+        return clients
+    }
+
+    /// Reads the unsuccessful transactions in the requested order. 
+    /// 
+    /// # Arguments 
+    /// * order (ErrorLogOrder): the order the transactions are returned in, the log itself is left in input order
+    /// 
+    /// # Returns 
+    /// * (Vec<&Transaction>): the unsuccessful transactions
+    pub fn error_log(&self, order: ErrorLogOrder) -> Vec<&Transaction> {
+        let mut errors: Vec<&Transaction> = self.total_error_transaction_log.iter().map(|(transaction, _)| transaction).collect();
+        if order == ErrorLogOrder::ClientThenTx {
+            // the sort is stable so repeated rejections of the same transaction keep their input order
+            errors.sort_by_key(|transaction| (transaction.client, transaction.tx));
+        }
+        return errors
+    }
+
+    /// Drains the unsuccessful transactions so a long running consumer can collect them periodically. 
+    /// 
+    /// # Returns 
+    /// * (Vec<(Transaction, TransactionError)>): the unsuccessful transactions and why they failed in the order they were added, the log is left empty
+    pub fn take_error_log(&mut self) -> Vec<(Transaction, TransactionError)> {
+        return std::mem::take(&mut self.total_error_transaction_log)
+    }
+
+    /// Drains the warnings so a long running consumer can report them periodically. 
+    /// 
+    /// # Returns 
+    /// * (Vec<(Transaction, TransactionWarning)>): the transactions that had a warning and what it was in the order they were added, the log is left empty
+    pub fn take_warning_log(&mut self) -> Vec<(Transaction, TransactionWarning)> {
+        return std::mem::take(&mut self.total_warning_log)
+    }
+
+    /// Applies every transaction in the error log again, in the order they were rejected, so those that can now go through are accepted. 
+    /// 
+    /// # Returns 
+    /// * (RetryReport): how many were retried and how many were accepted or rejected again
+    pub fn retry_errors(&mut self) -> RetryReport {
+        let pending = self.take_error_log();
+        let accepted_before = self.accepted_count;
+        let mut account_map = std::mem::take(self);
+
+        for (transaction, _) in pending.iter().cloned() {
+            account_map = account_map.add_transaction(transaction);
+        }
+        *self = account_map;
+        return RetryReport{
+            retried: pending.len(),
+            succeeded: self.accepted_count - accepted_before,
+            still_failing: self.total_error_transaction_log.len()
+        }
+    }
+
+    /// Converts the accounts into the rows written to CSV. 
+    /// 
+    /// # Returns 
+    /// * (Vec<AccountSchema>): a row for every account sorted by client ID
+    pub fn to_schemas(&self) -> Vec<AccountSchema> {
+        let mut schemas: Vec<AccountSchema> = self.accounts.values().map(AccountSchema::convert_from_account).collect();
+        schemas.sort_by_key(|schema| schema.client);
+        return schemas
+    }
+
+    /// Finds the account with the most funds held by disputes, the biggest current exposure. 
+    /// 
+    /// # Returns 
+    /// * (Option<(ClientId, f32)>): the client and its held amount, the lowest client ID winning a tie, None if there are no accounts
+    pub fn max_held(&self) -> Option<(ClientId, f32)> {
+        let mut largest: Option<(ClientId, f32)> = None;
+
+        for account in self.accounts.values() {
+            let candidate = (account.client_id(), account.held());
+            largest = match largest {
+                Some(current) if current.1 > candidate.1 || (current.1 == candidate.1 && current.0 < candidate.0) => Some(current),
+                _ => Some(candidate)
+            };
+        }
+        return largest
+    }
+
+    /// Sums the absolute amounts of every accepted deposit and withdrawal, disputes, resolves and chargebacks do not move new money. 
+    /// 
+    /// # Returns 
+    /// * (f32): the gross volume of the accepted transactions
+    pub fn gross_volume(&self) -> f32 {
+        return self.total_transaction_log.iter().filter(|transaction| {
+            transaction.transaction_type == DEPOSIT || transaction.transaction_type == WITHDRAWAL
+        }).map(|transaction| transaction.amount.unwrap_or(0.0).abs()).sum()
+    }
+
+    /// Groups the accepted transactions by client and transaction ID so the lifecycle of each deposit or withdrawal can be read together. 
+    /// Transaction IDs are only unique per client under the default scope so the client is part of the key. 
+    /// 
+    /// # Returns 
+    /// * (HashMap<(ClientId, TxId), Vec<Transaction>>): the accepted transactions for each client and transaction ID in the order they were applied
+    pub fn ledger_by_tx(&self) -> HashMap<(ClientId, TxId), Vec<Transaction>> {
+        let mut ledger: HashMap<(ClientId, TxId), Vec<Transaction>> = HashMap::new();
+
+        for transaction in &self.total_transaction_log {
+            ledger.entry((transaction.client, transaction.tx)).or_default().push(transaction.clone());
+        }
+        return ledger
+    }
+
+    /// Grows or shrinks the held funds of every account by a rate for modelling interest or decay, this is not part of normal processing. 
+    /// 
+    /// # Arguments 
+    /// * rate (f32): the change applied to every hold, 0.01 adds 1% and -0.01 removes 1%
+    pub fn apply_hold_adjustment(&mut self, rate: f32) {
+        for account in self.accounts.values_mut() {
+            account.apply_hold_adjustment(rate);
+        }
+    }
+
+    /// Finds the disputes, resolves and chargebacks that refer to a transaction ID no deposit or withdrawal of the same client in the stream used. 
+    /// A dispute of an unknown transaction is ignored rather than rejected so both the accepted and error logs are searched. 
+    /// 
+    /// # Returns 
+    /// * (Vec<Transaction>): the dangling references, those in the accepted log first, each log in the order it was added to
+    pub fn dangling_references(&self) -> Vec<Transaction> {
+        let all_transactions = || self.total_transaction_log.iter().chain(self.total_error_transaction_log.iter().map(|(transaction, _)| transaction));
+        let known_tx_ids: HashSet<(ClientId, TxId)> = all_transactions().filter(|transaction| {
+            transaction.transaction_type == DEPOSIT || transaction.transaction_type == WITHDRAWAL
+        }).map(|transaction| (transaction.client, transaction.tx)).collect();
+
+        return all_transactions().filter(|transaction| {
+            (transaction.transaction_type == DISPUTE || transaction.transaction_type == RESOLVE || transaction.transaction_type == CHARGEBACK)
+                && !known_tx_ids.contains(&(transaction.client, transaction.tx))
+        }).cloned().collect()
+    }
+
+    /// Builds a map from previously written account rows so processing can resume from their balances. 
+    /// The accounts start with empty transaction logs so disputes can not refer to transactions from before the rows were written. 
+    /// 
+    /// # Arguments 
+    /// * rows (impl IntoIterator<Item = AccountSchema>): the account rows, a later row for the same client replaces an earlier one
+    /// 
+    /// # Returns 
+    /// * (AccountMap): the map holding an account for every row
+    pub fn from_schemas(rows: impl IntoIterator<Item = AccountSchema>) -> AccountMap {
+        let mut account_map = AccountMap::new();
+
+        for row in rows {
+            let mut account = Account::new(ClientId(row.client));
+            account.first_seen_index = account_map.accounts.get(&ClientId(row.client)).map(|existing| existing.first_seen_index()).unwrap_or(account_map.accounts.len());
+            account.amount_available = row.available;
+            account.amount_held = row.held;
+            account.total = row.total;
+            account.locked = row.locked;
+            account.locked_by_tx = row.locked_by_tx.map(TxId);
+            account_map.accounts.insert(ClientId(row.client), account);
+        }
+        return account_map
+    }
+
+    /// Applies a final adjustment to every account before output, for example a rounding or minimum-balance sweep. 
+    /// 
+    /// # Arguments 
+    /// * f (F): the adjustment, called once for every account
+    pub fn finalize_with<F: Fn(&mut Account)>(&mut self, f: F) {
+        for account in self.accounts.values_mut() {
+            f(account);
+        }
+    }
+
+    /// Moves the accounts and logs of a map built from a separate set of clients into this one, used to join partitioned processing. 
+    /// 
+    /// # Arguments 
+    /// * partition (AccountMap): the map to merge in, its clients are not expected to have accounts in this map
+    pub fn merge(&mut self, partition: AccountMap) {
+        self.accounts.extend(partition.accounts);
+        self.total_transaction_log.extend(partition.total_transaction_log);
+        self.total_error_transaction_log.extend(partition.total_error_transaction_log);
+        self.total_warning_log.extend(partition.total_warning_log);
+        self.accepted_count += partition.accepted_count;
+        self.skipped_rows.extend(partition.skipped_rows);
+        for (tx, clients) in partition.used_tx_ids {
+            self.used_tx_ids.entry(tx).or_default().extend(clients);
+        }
+    }
+
+    /// Finds the accounts whose balances or locked state differ from an earlier snapshot so only they need to be pushed on. 
+    /// 
+    /// # Arguments 
+    /// * prev (&AccountMap): the earlier snapshot of the map
+    /// 
+    /// # Returns 
+    /// * (Vec<ClientId>): the changed or new clients in ascending order
+    pub fn changed_since(&self, prev: &AccountMap) -> Vec<ClientId> {
+        let mut changed: Vec<ClientId> = self.accounts.values().filter(|account| {
+            match prev.accounts.get(&account.client_id()) {
+                Some(previous) => {
+                    return previous.available() != account.available()
+                        || previous.held() != account.held()
+                        || previous.total() != account.total()
+                        || previous.is_locked() != account.is_locked()
+                },
+                None => {return true}
+            }
+        }).map(|account| account.client_id()).collect();
+        changed.sort();
+        return changed
+    }
+}
+
+
+#[cfg(test)]
+mod account_map_tests {
+
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+
+    use super::{AccountMap, AccountSchema, ErrorLogOrder, InvariantError, RetryReport};
+    use super::{ClientId, Transaction, TransactionError, TransactionWarning, TxId};
+    use super::super::events::{BalanceEvent, BalanceField, MinBalanceAlert};
+    use super::super::super::engine::config::{DuplicatePolicy, EngineConfig, ResolveTarget, TxIdScope};
+    use super::super::super::transactions::enums::TransactionType;
+    use super::super::super::transactions::enums::TransactionType::{CHARGEBACK, DEPOSIT, DISPUTE, FREEZE, RESOLVE, WITHDRAWAL};
+
+    #[test]
+    fn test_balance_events() {
+        let (sender, receiver) = channel();
+        let mut account_map = AccountMap::new().with_event_sink(sender);
+
+        let tx_one =   Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0));
+        let tx_two =   Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(50.0));
+        let tx_three = Transaction::new(DISPUTE,    ClientId(1), TxId(1), None);
+
+        account_map = account_map.add_transaction(tx_one);
+        account_map = account_map.add_transaction(tx_two);
+        account_map = account_map.add_transaction(tx_three);
+        drop(account_map);
+
+        let events: Vec<BalanceEvent> = receiver.iter().collect();
+
+        assert_eq!(vec![
+            BalanceEvent{client: ClientId(1), tx: TxId(1), field: BalanceField::Available, before: 0.0, after: 5.0, delta: 5.0},
+            BalanceEvent{client: ClientId(1), tx: TxId(1), field: BalanceField::Total, before: 0.0, after: 5.0, delta: 5.0},
+            BalanceEvent{client: ClientId(1), tx: TxId(1), field: BalanceField::Available, before: 5.0, after: 0.0, delta: -5.0},
+            BalanceEvent{client: ClientId(1), tx: TxId(1), field: BalanceField::Held, before: 0.0, after: 5.0, delta: 5.0}
+        ], events);
+    }
+
+    #[test]
+    fn test_negative_available() {
+        let mut account_map = AccountMap::new();
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT,    ClientId(2), TxId(1), Some(10.0)));
+        account_map = account_map.add_transaction(Transaction::new(WITHDRAWAL, ClientId(2), TxId(2), Some(8.0)));
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT,    ClientId(1), TxId(3), Some(1.0)));
+
+        assert!(account_map.negative_available().is_empty());
+
+        account_map = account_map.add_transaction(Transaction::new(DISPUTE,    ClientId(2), TxId(1), None));
+        assert_eq!(vec![ClientId(2)], account_map.negative_available());
+    }
+
+    #[test]
+    fn test_locked_accounts() {
+        let mut account_map = AccountMap::new();
+        for client in [4, 2, 3, 1] {
+            account_map = account_map.add_transaction(Transaction::new(DEPOSIT, ClientId(client), TxId(client), Some(1.0)));
+        }
+        for client in [4, 1] {
+            account_map = account_map.add_transaction(Transaction::new(FREEZE, ClientId(client), TxId(10 + client), None));
+        }
+
+        let locked: Vec<ClientId> = account_map.locked_accounts().iter().map(|account| account.client_id()).collect();
+        assert_eq!(vec![ClientId(1), ClientId(4)], locked);
+    }
+
+    #[test]
+    fn test_detect_double_spend() {
+        let transactions = vec![
+            // client 1 withdraws the deposit and then charges it back
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(10.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(10.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(10.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None),
+            // client 2 charges back without withdrawing
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(4), Some(10.0)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(4), None),
+            Transaction::new(CHARGEBACK, ClientId(2), TxId(4), None),
+            // client 3 withdraws without a chargeback
+            Transaction::new(DEPOSIT,    ClientId(3), TxId(5), Some(10.0)),
+            Transaction::new(WITHDRAWAL, ClientId(3), TxId(6), Some(5.0))
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        assert_eq!(vec![ClientId(1)], account_map.detect_double_spend());
+        assert_eq!(0.0, account_map.accounts[&ClientId(1)].total());
+    }
+
+    #[test]
+    fn test_error_log_order() {
+        let transactions = vec![
+            Transaction::new(WITHDRAWAL, ClientId(2), TxId(4), Some(1.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(1.0)),
+            Transaction::new(WITHDRAWAL, ClientId(2), TxId(1), Some(1.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(1.0))
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        let input_order: Vec<(u32, u32)> = account_map.error_log(ErrorLogOrder::Input).iter().map(|transaction| (transaction.client.0, transaction.tx.0)).collect();
+        assert_eq!(vec![(2, 4), (1, 3), (2, 1), (1, 2)], input_order);
+        assert_eq!(input_order, account_map.total_error_transaction_log.iter().map(|(transaction, _)| (transaction.client.0, transaction.tx.0)).collect::<Vec<(u32, u32)>>());
+
+        let sorted: Vec<(u32, u32)> = account_map.error_log(ErrorLogOrder::ClientThenTx).iter().map(|transaction| (transaction.client.0, transaction.tx.0)).collect();
+        assert_eq!(vec![(1, 2), (1, 3), (2, 1), (2, 4)], sorted);
+    }
+
+    #[test]
+    fn test_to_schemas() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(3), TxId(1), Some(3.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(1.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(3), Some(2.0)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(3), None),
+            Transaction::new(FREEZE,     ClientId(3), TxId(4), None)
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        let rows: Vec<(u32, f32, f32, f32, bool)> = account_map.to_schemas().iter().map(|schema| {
+            (schema.client, schema.available, schema.held, schema.total, schema.locked)
+        }).collect();
+        assert_eq!(vec![(1, 1.0, 0.0, 1.0, false), (2, 0.0, 2.0, 2.0, false), (3, 3.0, 0.0, 3.0, true)], rows);
+        assert!(account_map.to_schemas().iter().all(|schema| schema.tx_count.is_none()));
+    }
+
+    #[test]
+    fn test_tx_id_collision() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(1), Some(2.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(1), Some(3.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None)
+        ];
+
+        let mut global = AccountMap::new().with_config(EngineConfig::builder().tx_id_scope(TxIdScope::Global).build());
+        for transaction in transactions.clone() {
+            global = global.add_transaction(transaction);
+        }
+        assert_eq!(2, global.total_error_transaction_log.len());
+        assert_eq!(0.0, global.accounts[&ClientId(1)].available());
+        assert_eq!(5.0, global.accounts[&ClientId(1)].held());
+        assert!(!global.accounts.contains_key(&ClientId(2)));
+
+        // by default the ID only has to be unique for each client
+        let mut per_client = AccountMap::new();
+        for transaction in transactions {
+            per_client = per_client.add_transaction(transaction);
+        }
+        assert_eq!(vec![(WITHDRAWAL, ClientId(1))], per_client.total_error_transaction_log.iter().map(|(transaction, _)| (transaction.transaction_type.clone(), transaction.client)).collect::<Vec<_>>());
+        assert_eq!(3.0, per_client.accounts[&ClientId(2)].available());
+    }
+
+    #[test]
+    fn test_max_held() {
+        assert_eq!(None, AccountMap::new().max_held());
+
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(3), TxId(1), Some(4.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(2.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(3), Some(4.0)),
+            Transaction::new(DISPUTE,    ClientId(3), TxId(1), None),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(2), None),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(3), None)
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        // clients 2 and 3 hold the same amount so the lower ID wins
+        assert_eq!(Some((ClientId(2), 4.0)), account_map.max_held());
+    }
+
+    #[test]
+    fn test_duplicate_policy() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(3.0))
+        ];
+        let expected = vec![
+            (DuplicatePolicy::Reject, 5.0, 1, vec![]),
+            (DuplicatePolicy::FirstWins, 5.0, 0, vec![TransactionWarning::DuplicateDropped]),
+            (DuplicatePolicy::LastWins, 3.0, 0, vec![])
+        ];
+
+        for (duplicate_policy, total, errors, warnings) in expected {
+            let config = EngineConfig::builder().duplicate_policy(duplicate_policy).build();
+            let mut account_map = AccountMap::new().with_config(config);
+            for transaction in transactions.clone() {
+                account_map = account_map.add_transaction(transaction);
+            }
+            assert_eq!(total, account_map.accounts[&ClientId(1)].total(), "{:?}", duplicate_policy);
+            assert_eq!(total, account_map.accounts[&ClientId(1)].available(), "{:?}", duplicate_policy);
+            assert_eq!(errors, account_map.total_error_transaction_log.len(), "{:?}", duplicate_policy);
+            let logged: Vec<TransactionWarning> = account_map.take_warning_log().into_iter().map(|(_, warning)| warning).collect();
+            assert_eq!(warnings, logged, "{:?}", duplicate_policy);
+            // only the transaction that counts is left in the log
+            let amounts: Vec<Option<f32>> = account_map.total_transaction_log.iter().map(|transaction| transaction.amount).collect();
+            assert_eq!(vec![Some(total)], amounts, "{:?}", duplicate_policy);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_last_wins_rejected_replacement() {
+        let config = EngineConfig::builder().duplicate_policy(DuplicatePolicy::LastWins).build();
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(10.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(0.0))
+        ];
+        let mut account_map = AccountMap::new().with_config(config);
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        // the replacement is rejected so the original is kept as it was
+        assert_eq!(10.0, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(10.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(1, account_map.total_error_transaction_log.len());
+        assert_eq!(TransactionError::ZeroAmount, account_map.total_error_transaction_log[0].1);
+        assert_eq!(1, account_map.total_transaction_log.len());
+        assert_eq!(Some(10.0), account_map.total_transaction_log[0].amount);
+    }
+
+    #[test]
+    fn test_duplicate_last_wins_resolved_stay_held() {
+        let config = EngineConfig::builder()
+            .duplicate_policy(DuplicatePolicy::LastWins)
+            .resolve_target(ResolveTarget::StayHeld)
+            .build();
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(RESOLVE,    ClientId(1), TxId(1), None),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(3.0))
+        ];
+        let mut account_map = AccountMap::new().with_config(config);
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        // undoing the deposit would leave its funds held with nothing behind them
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].held());
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(TransactionError::AlreadyResolved, account_map.total_error_transaction_log[0].1);
+    }
+
+    #[test]
+    fn test_duplicate_last_wins_disputed() {
+        let config = EngineConfig::builder().duplicate_policy(DuplicatePolicy::LastWins).build();
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(3.0))
+        ];
+        let mut account_map = AccountMap::new().with_config(config);
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        // a disputed transaction can not be replaced so the duplicate is rejected
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].held());
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(1, account_map.total_error_transaction_log.len());
+    }
+
+    #[test]
+    fn test_ledger_by_tx() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(1.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(9.0)),
+            Transaction::new(RESOLVE,    ClientId(1), TxId(1), None)
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+        let ledger = account_map.ledger_by_tx();
+
+        let lifecycle: Vec<TransactionType> = ledger[&(ClientId(1), TxId(1))].iter().map(|transaction| transaction.transaction_type.clone()).collect();
+        assert_eq!(vec![DEPOSIT, DISPUTE, RESOLVE], lifecycle);
+        assert_eq!(1, ledger[&(ClientId(1), TxId(2))].len());
+        // the rejected withdrawal is not part of the ledger
+        assert!(!ledger.contains_key(&(ClientId(1), TxId(3))));
+    }
+
+    #[test]
+    fn test_ledger_by_tx_per_client() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(5), Some(5.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(5), Some(3.0)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(5), None)
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+        let ledger = account_map.ledger_by_tx();
+
+        let first: Vec<TransactionType> = ledger[&(ClientId(1), TxId(5))].iter().map(|transaction| transaction.transaction_type.clone()).collect();
+        let second: Vec<TransactionType> = ledger[&(ClientId(2), TxId(5))].iter().map(|transaction| transaction.transaction_type.clone()).collect();
+        assert_eq!(vec![DEPOSIT], first);
+        assert_eq!(vec![DEPOSIT, DISPUTE], second);
+    }
+
+    #[test]
+    fn test_routes_by_client() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(7), TxId(1), Some(2.0)),
+            Transaction::new(DEPOSIT,    ClientId(3), TxId(2), Some(4.0)),
+            Transaction::new(WITHDRAWAL, ClientId(7), TxId(3), Some(1.0))
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        assert_eq!(2, account_map.accounts.len());
+        assert_eq!(ClientId(7), account_map.accounts[&ClientId(7)].client_id());
+        assert_eq!(1.0, account_map.accounts[&ClientId(7)].total());
+        assert_eq!(2, account_map.accounts[&ClientId(7)].transaction_log.len());
+        assert_eq!(4.0, account_map.accounts[&ClientId(3)].total());
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(99), None),
+            Transaction::new(CHARGEBACK, ClientId(2), TxId(98), None),
+            // a rejected withdrawal still counts as a transaction seen in the stream
+            Transaction::new(WITHDRAWAL, ClientId(2), TxId(2), Some(5.0)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(2), None)
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        let dangling: Vec<(TransactionType, u32)> = account_map.dangling_references().iter().map(|transaction| (transaction.transaction_type.clone(), transaction.tx.0)).collect();
+        assert_eq!(vec![(DISPUTE, 99), (CHARGEBACK, 98)], dangling);
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].held());
+    }
+
+    #[test]
+    fn test_dangling_references_per_client() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            // client 2 never used tx 1 so the dispute refers to nothing of its own
+            Transaction::new(DISPUTE,    ClientId(2), TxId(1), None)
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        let dangling: Vec<(ClientId, u32)> = account_map.dangling_references().iter().map(|transaction| (transaction.client, transaction.tx.0)).collect();
+        assert_eq!(vec![(ClientId(2), 1)], dangling);
+    }
+
+    #[test]
+    fn test_from_schemas() {
+        let rows = vec![
+            AccountSchema{client: 1, available: 3.0, held: 0.0, total: 3.0, locked: false, tx_count: None, locked_by_tx: None, scale: None},
+            AccountSchema{client: 2, available: 1.0, held: 0.0, total: 1.0, locked: true, tx_count: Some(4), locked_by_tx: None, scale: None}
+        ];
+        let mut account_map = AccountMap::from_schemas(rows);
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(10), Some(2.0)));
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT, ClientId(2), TxId(11), Some(2.0)));
+
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(1, account_map.accounts[&ClientId(1)].transaction_log.len());
+        // the locked state is carried over so the deposit on client 2 is rejected
+        assert_eq!(1.0, account_map.accounts[&ClientId(2)].total());
+        assert_eq!(1, account_map.total_error_transaction_log.len());
+    }
+
+    #[test]
+    fn test_first_seen_index() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(9), TxId(1), Some(1.0)),
+            // a rejected first transaction does not create the account
+            Transaction::new(WITHDRAWAL, ClientId(4), TxId(2), Some(1.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(3), Some(1.0)),
+            Transaction::new(DEPOSIT,    ClientId(9), TxId(4), Some(1.0)),
+            Transaction::new(DEPOSIT,    ClientId(4), TxId(5), Some(1.0))
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        assert_eq!(0, account_map.accounts[&ClientId(9)].first_seen_index());
+        assert_eq!(1, account_map.accounts[&ClientId(2)].first_seen_index());
+        assert_eq!(2, account_map.accounts[&ClientId(4)].first_seen_index());
+    }
+
+    #[test]
+    fn test_finalize_with() {
+        let rows = vec![
+            AccountSchema{client: 1, available: 1e-10, held: 0.0, total: 1e-10, locked: false, tx_count: None, locked_by_tx: None, scale: None},
+            AccountSchema{client: 2, available: 2.5, held: 1e-12, total: 2.5, locked: false, tx_count: None, locked_by_tx: None, scale: None}
+        ];
+        let mut account_map = AccountMap::from_schemas(rows);
+
+        account_map.finalize_with(|account| {
+            for balance in [&mut account.amount_available, &mut account.amount_held, &mut account.total] {
+                if balance.abs() < 1e-9 {
+                    *balance = 0.0;
+                }
+            }
+        });
+
+        assert_eq!(0.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(0.0, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(2.5, account_map.accounts[&ClientId(2)].available());
+        assert_eq!(0.0, account_map.accounts[&ClientId(2)].held());
+    }
+
+    #[test]
+    fn test_changed_since() {
+        let mut account_map = AccountMap::new();
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(1.0)));
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT, ClientId(2), TxId(2), Some(1.0)));
+        let snapshot = account_map.clone();
+        assert!(account_map.changed_since(&snapshot).is_empty());
+
+        account_map = account_map.add_transaction(Transaction::new(DISPUTE, ClientId(2), TxId(2), None));
+        // a rejected transaction does not change client 1
+        account_map = account_map.add_transaction(Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(5.0)));
+        assert_eq!(vec![ClientId(2)], account_map.changed_since(&snapshot));
+
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT, ClientId(3), TxId(4), Some(1.0)));
+        assert_eq!(vec![ClientId(2), ClientId(3)], account_map.changed_since(&snapshot));
+    }
+
+    #[test]
+    fn test_shared_tx_id_isolation() {
+        let mut account_map = AccountMap::new();
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(5), Some(10.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(5), Some(20.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(5), None)
+        ];
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+        assert_eq!((0.0, 10.0, 10.0), (account_map.accounts[&ClientId(1)].available(), account_map.accounts[&ClientId(1)].held(), account_map.accounts[&ClientId(1)].total()));
+        assert_eq!((20.0, 0.0, 20.0), (account_map.accounts[&ClientId(2)].available(), account_map.accounts[&ClientId(2)].held(), account_map.accounts[&ClientId(2)].total()));
+
+        // client 2's tx 5 is not under dispute so resolving or charging it back changes nothing, for either client
+        account_map = account_map.add_transaction(Transaction::new(RESOLVE, ClientId(2), TxId(5), None));
+        account_map = account_map.add_transaction(Transaction::new(CHARGEBACK, ClientId(2), TxId(5), None));
+        assert_eq!(10.0, account_map.accounts[&ClientId(1)].held());
+        assert_eq!((20.0, 0.0, 20.0), (account_map.accounts[&ClientId(2)].available(), account_map.accounts[&ClientId(2)].held(), account_map.accounts[&ClientId(2)].total()));
+        assert!(!account_map.accounts[&ClientId(2)].is_locked());
+
+        account_map = account_map.add_transaction(Transaction::new(CHARGEBACK, ClientId(1), TxId(5), None));
+        assert!(account_map.accounts[&ClientId(1)].is_locked());
+        assert_eq!(0.0, account_map.accounts[&ClientId(1)].total());
+        assert!(!account_map.accounts[&ClientId(2)].is_locked());
+        assert_eq!((20.0, 0.0, 20.0), (account_map.accounts[&ClientId(2)].available(), account_map.accounts[&ClientId(2)].held(), account_map.accounts[&ClientId(2)].total()));
+
+        // a dispute from a client that never used tx 5 does not reach the other accounts
+        account_map = account_map.add_transaction(Transaction::new(DISPUTE, ClientId(3), TxId(5), None));
+        assert_eq!(0.0, account_map.accounts[&ClientId(2)].held());
+        assert_eq!(0.0, account_map.accounts[&ClientId(3)].held());
+    }
+
+    #[test]
+    fn test_default_deposit_amount() {
+        let deposit = Transaction::new(DEPOSIT, ClientId(1), TxId(1), None);
+
+        let account_map = AccountMap::new().add_transaction(deposit.clone());
+        assert_eq!(1, account_map.total_error_transaction_log.len());
+        assert!(!account_map.accounts.contains_key(&ClientId(1)));
+
+        let config = EngineConfig::builder().default_deposit_amount(Some(25.0)).build();
+        let account_map = AccountMap::new().with_config(config).add_transaction(deposit);
+        assert!(account_map.total_error_transaction_log.is_empty());
+        assert_eq!(25.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(Some(25.0), account_map.total_transaction_log[0].amount);
+    }
+
+    #[test]
+    fn test_take_error_log() {
+        let mut account_map = AccountMap::new();
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(1.0)));
+        account_map = account_map.add_transaction(Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(5.0)));
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(1.0)));
+
+        let taken: Vec<(u32, u32, TransactionError)> = account_map.take_error_log().into_iter().map(|(transaction, error)| {
+            (transaction.client.0, transaction.tx.0, error)
+        }).collect();
+        assert_eq!(vec![(1, 2, TransactionError::InsufficientFunds), (1, 1, TransactionError::DuplicateTxId)], taken);
+        assert!(account_map.take_error_log().is_empty());
+        assert!(account_map.total_error_transaction_log.is_empty());
+    }
+
+    #[test]
+    fn test_retry_errors() {
+        let mut account_map = AccountMap::new();
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(2.0)));
+        account_map = account_map.add_transaction(Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(5.0)));
+        account_map = account_map.add_transaction(Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(50.0)));
+        assert_eq!(2, account_map.total_error_transaction_log.len());
+
+        // the late deposit covers the first withdrawal but not the second
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(4), Some(4.0)));
+        let report = account_map.retry_errors();
+
+        assert_eq!(RetryReport{retried: 2, succeeded: 1, still_failing: 1}, report);
+        assert_eq!(1.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(vec![TxId(1), TxId(4), TxId(2)], account_map.total_transaction_log.iter().map(|transaction| transaction.tx).collect::<Vec<TxId>>());
+        assert_eq!(TxId(3), account_map.total_error_transaction_log[0].0.tx);
+    }
+
+    #[test]
+    fn test_account_by_client_id() {
+        let account_map = AccountMap::new().add_transaction(Transaction::new(DEPOSIT, ClientId(3), TxId(9), Some(1.0)));
+
+        assert_eq!(Some(1.0), account_map.account(ClientId(3)).map(|account| account.total()));
+        assert!(account_map.account(ClientId(9)).is_none());
+        assert!(account_map.account(ClientId(u32::MAX)).is_none());
+    }
+
+    #[test]
+    fn test_gross_volume() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(10.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(4.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(3), Some(2.5)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(3), None),
+            Transaction::new(RESOLVE,    ClientId(2), TxId(3), Some(2.5)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(CHARGEBACK, ClientId(1), TxId(1), None),
+            // rejected so it is not part of the volume
+            Transaction::new(WITHDRAWAL, ClientId(2), TxId(4), Some(100.0))
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        assert_eq!(16.5, account_map.gross_volume());
+        assert_eq!(0.0, AccountMap::new().gross_volume());
+    }
+
+    #[test]
+    fn test_write_client_summaries() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(1), Some(10.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(2), Some(5.0)),
+            Transaction::new(WITHDRAWAL, ClientId(2), TxId(3), Some(3.0)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(2), None),
+            Transaction::new(RESOLVE,    ClientId(2), TxId(2), None),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(4), Some(4.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(4), None),
+            Transaction::new(CHARGEBACK, ClientId(1), TxId(4), None)
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+        let mut output: Vec<u8> = Vec::new();
+        account_map.write_client_summaries(&mut output).unwrap();
+
+        assert_eq!(
+            "client,deposits,withdrawals,disputes,resolves,chargebacks,available,held,total,locked\n\
+             1,1,0,1,0,1,0.0,0.0,0.0,true\n\
+             2,2,1,1,1,0,12.0,0.0,12.0,false\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_negative_deposit_as_withdrawal() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(10.0)),
+            Transaction::new(DEPOSIT, ClientId(1), TxId(2), Some(-5.0))
+        ];
+
+        let mut spec = AccountMap::new();
+        for transaction in transactions.clone() {
+            spec = spec.add_transaction(transaction);
+        }
+        assert_eq!(10.0, spec.accounts[&ClientId(1)].available());
+        assert_eq!(TransactionError::NegativeDeposit, spec.total_error_transaction_log[0].1);
+
+        let config = EngineConfig::builder().negative_deposit_as_withdrawal(true).build();
+        let mut signed = AccountMap::new().with_config(config);
+        for transaction in transactions {
+            signed = signed.add_transaction(transaction);
+        }
+        assert_eq!(5.0, signed.accounts[&ClientId(1)].available());
+        assert_eq!(5.0, signed.accounts[&ClientId(1)].total());
+        assert_eq!((WITHDRAWAL, Some(5.0)), (signed.total_transaction_log[1].transaction_type.clone(), signed.total_transaction_log[1].amount));
+    }
+
+    #[test]
+    fn test_inconsistent_held() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DISPUTE, ClientId(1), TxId(1), None),
+            Transaction::new(DEPOSIT, ClientId(2), TxId(2), Some(5.0)),
+            Transaction::new(DISPUTE, ClientId(2), TxId(2), None),
+            Transaction::new(RESOLVE, ClientId(2), TxId(2), None),
+            Transaction::new(DEPOSIT, ClientId(3), TxId(3), Some(5.0))
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+        assert!(account_map.inconsistent_held().is_empty());
+
+        // funds held without the dispute that should explain them
+        account_map.accounts.get_mut(&ClientId(3)).unwrap().amount_held = 1.0;
+        account_map.accounts.get_mut(&ClientId(1)).unwrap().held_by_tx.clear();
+        assert_eq!(vec![ClientId(1), ClientId(3)], account_map.inconsistent_held());
+    }
+
+    #[test]
+    fn test_assert_invariants() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DISPUTE,    ClientId(1), TxId(1), None),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(2), Some(3.0)),
+            Transaction::new(DEPOSIT,    ClientId(3), TxId(3), Some(1.0))
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+        assert_eq!(Ok(()), account_map.assert_invariants());
+
+        account_map.accounts.get_mut(&ClientId(3)).unwrap().total = 4.0;
+        account_map.accounts.get_mut(&ClientId(2)).unwrap().amount_held = 1.0;
+        assert_eq!(Err(vec![
+            InvariantError::TotalMismatch{client: ClientId(2), available: 3.0, held: 1.0, total: 3.0},
+            InvariantError::TotalMismatch{client: ClientId(3), available: 1.0, held: 0.0, total: 4.0}
+        ]), account_map.assert_invariants());
+    }
+
+    #[test]
+    fn test_min_balance_alert() {
+        let alerts: Arc<Mutex<Vec<MinBalanceAlert>>> = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&alerts);
+        let mut account_map = AccountMap::new().with_min_balance_alert(5.0, move |alert| recorded.lock().unwrap().push(alert.clone()));
+
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(10.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(3.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(4.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(4), Some(1.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(5), Some(1.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(6), Some(2.0))
+        ];
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+
+        // only tx 3 crosses the minimum, the later withdrawals stay below it and a new account starting below it never crossed
+        assert_eq!(vec![MinBalanceAlert{client: ClientId(1), tx: TxId(3), minimum: 5.0, available: 3.0}], *alerts.lock().unwrap());
+    }
 }
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use super::account::AccountSnapshot;
+use super::super::transactions::ids::{ClientId, TxId};
+
+
+/// This enum is responsible for defining the balance fields of an account that can change.
+///
+/// # Attributes
+/// * Available: the funds available to the client
+/// * Held: the funds held for dispute
+/// * Total: the total funds of the client
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BalanceField {
+    Available,
+    Held,
+    Total
+}
+
+
+/// This struct is responsible for describing a single change to a balance field of an account.
+///
+/// # Attributes
+/// * client (ClientId): the ID of the account that changed
+/// * tx (TxId): the ID of the transaction that caused the change
+/// * field (BalanceField): the balance field that changed
+/// * before (f32): the value of the field before the transaction
+/// * after (f32): the value of the field after the transaction
+/// * delta (f32): after - before
+#[derive(Debug, PartialEq, Clone)]
+pub struct BalanceEvent {
+    pub client: ClientId,
+    pub tx: TxId,
+    pub field: BalanceField,
+    pub before: f32,
+    pub after: f32,
+    pub delta: f32
+}
+
+impl BalanceEvent {
+
+    /// Compares the state of an account before and after a transaction producing an event for every changed field.
+    ///
+    /// # Arguments
+    /// * before (&AccountSnapshot): the balances before the transaction was applied
+    /// * after (&AccountSnapshot): the balances after the transaction was applied
+    /// * tx (TxId): the ID of the transaction that was applied
+    ///
+    /// # Returns
+    /// * (Vec<BalanceEvent>): the events in the order available, held, total
+    pub fn diff(before: &AccountSnapshot, after: &AccountSnapshot, tx: TxId) -> Vec<BalanceEvent> {
+        let fields = [
+            (BalanceField::Available, before.available, after.available),
+            (BalanceField::Held, before.held, after.held),
+            (BalanceField::Total, before.total, after.total)
+        ];
+        let mut events: Vec<BalanceEvent> = Vec::new();
+
+        for (field, old_value, new_value) in fields {
+            if old_value != new_value {
+                events.push(BalanceEvent{client: after.client, tx, field, before: old_value, after: new_value, delta: new_value - old_value});
+            }
+        }
+        return events
+    }
+}
+
+
+/// The callback fired with a MinBalanceAlert, shared so the map of accounts can still be cloned.
+pub type AlertCallback = Arc<dyn Fn(&MinBalanceAlert) + Send + Sync>;
+
+
+/// This struct is responsible for describing a transaction that took the available funds of an account below the configured minimum.
+///
+/// # Attributes
+/// * client (ClientId): the ID of the account
+/// * tx (TxId): the ID of the transaction that crossed the minimum
+/// * minimum (f32): the configured minimum
+/// * available (f32): the available funds after the transaction
+#[derive(Debug, PartialEq, Clone)]
+pub struct MinBalanceAlert {
+    pub client: ClientId,
+    pub tx: TxId,
+    pub minimum: f32,
+    pub available: f32
+}
+
+impl MinBalanceAlert {
+
+    /// Checks if a transaction took the available funds from at or above the minimum to below it, staying below is not a new alert.
+    ///
+    /// # Arguments
+    /// * before (&AccountSnapshot): the balances before the transaction was applied
+    /// * after (&AccountSnapshot): the balances after the transaction was applied
+    /// * tx (TxId): the ID of the transaction that was applied
+    /// * minimum (f32): the configured minimum
+    ///
+    /// # Returns
+    /// * (Option<MinBalanceAlert>): the alert if the minimum was crossed
+    pub fn crossing(before: &AccountSnapshot, after: &AccountSnapshot, tx: TxId, minimum: f32) -> Option<MinBalanceAlert> {
+        if before.available >= minimum && after.available < minimum {
+            return Some(MinBalanceAlert{client: after.client, tx, minimum, available: after.available})
+        }
+        return None
+    }
+}
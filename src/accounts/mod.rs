@@ -1,8 +1,27 @@
 pub mod account;
 pub mod account_map;
+pub mod events;
+pub mod reconciliation;
+pub mod sharded;
+pub mod store;
 
 use account_map::AccountMap;
 use super::transactions::transaction::Transaction;
+use super::transactions::errors::TransactionError;
+
+
+/// This enum is responsible for describing what happened to a transaction passed to log_transaction. 
+/// 
+/// # Attributes 
+/// * Applied: the transaction was accepted by its account
+/// * Rejected: the transaction broke a rule and was put in the error log for the reason given
+/// * Dropped: the transaction was skipped without being logged, like a duplicate under DuplicatePolicy::FirstWins
+#[derive(Debug, PartialEq, Clone)]
+pub enum TransactionOutcome {
+    Applied,
+    Rejected(TransactionError),
+    Dropped
+}
 
 
 /// Entrypoint for logging a transaction to an account. 
@@ -12,8 +31,8 @@ use super::transactions::transaction::Transaction;
 /// * transaction_type (Transaction): the transaction to be logged 
 /// 
 /// # Returns 
-/// * (AccountMap): the updated map of all the accounts and transactions
-pub fn log_transaction(current_state: Option<AccountMap>, transaction: Transaction) -> AccountMap {
+/// * ((AccountMap, TransactionOutcome)): the updated map of all the accounts and transactions, and what happened to the transaction
+pub fn log_transaction(current_state: Option<AccountMap>, transaction: Transaction) -> (AccountMap, TransactionOutcome) {
 
     let mut account_state: AccountMap;
 
@@ -26,7 +45,51 @@ pub fn log_transaction(current_state: Option<AccountMap>, transaction: Transacti
         }
     }
 
-    account_state = account_state.add_transaction(transaction.clone(), transaction.client);
+    let accepted_before = account_state.accepted_count;
+    let rejected_before = account_state.total_error_transaction_log.len();
+    account_state = account_state.add_transaction(transaction);
+
+    let outcome = if account_state.accepted_count > accepted_before {
+        TransactionOutcome::Applied
+    }
+    else if account_state.total_error_transaction_log.len() > rejected_before {
+        TransactionOutcome::Rejected(account_state.total_error_transaction_log[rejected_before].1.clone())
+    }
+    else {
+        TransactionOutcome::Dropped
+    };
+    return (account_state, outcome)
+}
+
+#[cfg(test)]
+mod accounts_tests {
 
-    return account_state
-}
\ No newline at end of file
+    use super::{log_transaction, TransactionOutcome};
+    use super::Transaction;
+    use super::TransactionError;
+    use super::super::engine::config::{DuplicatePolicy, EngineConfig};
+    use super::account_map::AccountMap;
+    use super::super::transactions::enums::TransactionType::{DEPOSIT, WITHDRAWAL};
+    use super::super::transactions::ids::{ClientId, TxId};
+
+    #[test]
+    fn test_log_transaction_outcomes() {
+        let (account_map, outcome) = log_transaction(None, Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(2.0)));
+        assert_eq!(TransactionOutcome::Applied, outcome);
+
+        let (account_map, outcome) = log_transaction(Some(account_map), Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(5.0)));
+        assert_eq!(TransactionOutcome::Rejected(TransactionError::InsufficientFunds), outcome);
+        assert_eq!(2.0, account_map.accounts[&ClientId(1)].available());
+    }
+
+    #[test]
+    fn test_log_transaction_dropped() {
+        let config = EngineConfig::builder().duplicate_policy(DuplicatePolicy::FirstWins).build();
+        let account_map = AccountMap::new().with_config(config);
+        let (account_map, _) = log_transaction(Some(account_map), Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(2.0)));
+
+        let (account_map, outcome) = log_transaction(Some(account_map), Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(3.0)));
+        assert_eq!(TransactionOutcome::Dropped, outcome);
+        assert_eq!(2.0, account_map.accounts[&ClientId(1)].total());
+    }
+}
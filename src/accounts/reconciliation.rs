@@ -0,0 +1,175 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::account::Account;
+use super::account_map::AccountMap;
+use super::events::BalanceField;
+use super::super::transactions::amount::DEFAULT_EPSILON;
+use super::super::transactions::ids::ClientId;
+
+
+/// This struct is responsible for describing a balance that differs between two maps for the same client.
+///
+/// # Attributes
+/// * client (ClientId): the ID of the client
+/// * field (BalanceField): the balance that differs
+/// * a (f32): the value in the first map
+/// * b (f32): the value in the second map
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldDifference {
+    pub client: ClientId,
+    pub field: BalanceField,
+    pub a: f32,
+    pub b: f32
+}
+
+
+/// This struct is responsible for housing the differences between two maps of accounts.
+///
+/// # Attributes
+/// * only_in_a (Vec<ClientId>): clients that are only in the first map
+/// * only_in_b (Vec<ClientId>): clients that are only in the second map
+/// * differences (Vec<FieldDifference>): balances that differ by more than the epsilon
+/// * lock_differences (Vec<ClientId>): clients that are locked in one map but not the other
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ReconciliationReport {
+    pub only_in_a: Vec<ClientId>,
+    pub only_in_b: Vec<ClientId>,
+    pub differences: Vec<FieldDifference>,
+    pub lock_differences: Vec<ClientId>
+}
+
+impl ReconciliationReport {
+
+    /// Checks if the two maps agreed.
+    ///
+    /// # Returns
+    /// * (bool): true if no differences were found
+    pub fn is_empty(&self) -> bool {
+        return self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.differences.is_empty() && self.lock_differences.is_empty()
+    }
+}
+
+impl fmt::Display for ReconciliationReport {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<8} {:<10} {:>14} {:>14}", "client", "field", "a", "b")?;
+        for difference in &self.differences {
+            writeln!(f, "{:<8} {:<10} {:>14} {:>14}", difference.client, format!("{:?}", difference.field).to_lowercase(), difference.a, difference.b)?;
+        }
+        for client in &self.lock_differences {
+            writeln!(f, "{:<8} {:<10} {:>14} {:>14}", client, "locked", "differs", "differs")?;
+        }
+        for client in &self.only_in_a {
+            writeln!(f, "{:<8} only in a", client)?;
+        }
+        for client in &self.only_in_b {
+            writeln!(f, "{:<8} only in b", client)?;
+        }
+        return Ok(())
+    }
+}
+
+
+/// Compares two maps of accounts using the default epsilon.
+///
+/// # Arguments
+/// * a (&AccountMap): the first map
+/// * b (&AccountMap): the second map
+///
+/// # Returns
+/// * (ReconciliationReport): the differences ordered by client ID
+pub fn reconcile(a: &AccountMap, b: &AccountMap) -> ReconciliationReport {
+    return reconcile_with_epsilon(a, b, DEFAULT_EPSILON)
+}
+
+
+/// Compares two maps of accounts.
+///
+/// # Arguments
+/// * a (&AccountMap): the first map
+/// * b (&AccountMap): the second map
+/// * epsilon (f32): balances closer than this are treated as equal
+///
+/// # Returns
+/// * (ReconciliationReport): the differences ordered by client ID
+pub fn reconcile_with_epsilon(a: &AccountMap, b: &AccountMap, epsilon: f32) -> ReconciliationReport {
+    let clients: BTreeSet<ClientId> = a.accounts.keys().chain(b.accounts.keys()).cloned().collect();
+    let mut report = ReconciliationReport::default();
+
+    for client in clients {
+        match (a.accounts.get(&client), b.accounts.get(&client)) {
+            (Some(account_a), Some(account_b)) => {
+                compare_accounts(account_a, account_b, epsilon, &mut report);
+            },
+            (Some(_), None) => {report.only_in_a.push(client)},
+            (None, Some(_)) => {report.only_in_b.push(client)},
+            (None, None) => {}
+        }
+    }
+    return report
+}
+
+
+/// Adds the differences between two versions of the same account to the report.
+///
+/// # Arguments
+/// * account_a (&Account): the account from the first map
+/// * account_b (&Account): the account from the second map
+/// * epsilon (f32): balances closer than this are treated as equal
+/// * report (&mut ReconciliationReport): the report the differences are added to
+fn compare_accounts(account_a: &Account, account_b: &Account, epsilon: f32, report: &mut ReconciliationReport) {
+    let fields = [
+        (BalanceField::Available, account_a.available(), account_b.available()),
+        (BalanceField::Held, account_a.held(), account_b.held()),
+        (BalanceField::Total, account_a.total(), account_b.total())
+    ];
+    for (field, value_a, value_b) in fields {
+        if (value_a - value_b).abs() > epsilon {
+            report.differences.push(FieldDifference{client: account_a.client_id(), field, a: value_a, b: value_b});
+        }
+    }
+    if account_a.is_locked() != account_b.is_locked() {
+        report.lock_differences.push(account_a.client_id());
+    }
+}
+
+
+#[cfg(test)]
+mod reconciliation_tests {
+
+    use super::{reconcile, FieldDifference};
+    use super::super::account_map::AccountMap;
+    use super::super::events::BalanceField;
+    use super::super::super::transactions::transaction::Transaction;
+    use super::super::super::transactions::enums::TransactionType::{DEPOSIT, DISPUTE};
+    use super::super::super::transactions::ids::{ClientId, TxId};
+
+    #[test]
+    fn test_reconcile_held_difference() {
+        let deposit_one = Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(5.0));
+        let deposit_two = Transaction::new(DEPOSIT, ClientId(2), TxId(2), Some(3.0));
+        let dispute = Transaction::new(DISPUTE, ClientId(2), TxId(2), None);
+
+        let mut map_a = AccountMap::new();
+        map_a = map_a.add_transaction(deposit_one.clone());
+        map_a = map_a.add_transaction(deposit_two.clone());
+
+        let mut map_b = AccountMap::new();
+        map_b = map_b.add_transaction(deposit_one);
+        map_b = map_b.add_transaction(deposit_two);
+        map_b = map_b.add_transaction(dispute);
+
+        let report = reconcile(&map_a, &map_b);
+
+        assert!(report.only_in_a.is_empty());
+        assert!(report.only_in_b.is_empty());
+        assert!(report.lock_differences.is_empty());
+        assert_eq!(vec![
+            FieldDifference{client: ClientId(2), field: BalanceField::Available, a: 3.0, b: 0.0},
+            FieldDifference{client: ClientId(2), field: BalanceField::Held, a: 0.0, b: 3.0}
+        ], report.differences);
+        assert!(report.to_string().contains("held"));
+        assert!(reconcile(&map_a, &map_a).is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+use std::mem;
+use std::sync::{Mutex, PoisonError};
+
+use super::account_map::AccountMap;
+use super::super::engine::config::EngineConfig;
+use super::super::transactions::errors::TransactionError;
+use super::super::transactions::ids::ClientId;
+use super::super::transactions::transaction::Transaction;
+
+
+/// This struct is responsible for holding accounts behind a lock per shard so transactions can be applied from several threads.
+/// A client always lands in the same shard so its transactions are applied in the order apply is called for them,
+/// while clients in other shards are updated at the same time.
+///
+/// Each shard is a map of accounts of its own so duplicate transaction IDs are only detected within a shard, TxIdScope::Global
+/// is only exact for IDs used by clients in the same shard the same as process_parallel.
+///
+/// # Attributes
+/// * shards (Vec<Mutex<AccountMap>>): the accounts and logs split by client ID across the shards
+/// * config (EngineConfig): the policies applied to every transaction
+pub struct ShardedAccounts {
+    shards: Vec<Mutex<AccountMap>>,
+    config: EngineConfig
+}
+
+impl ShardedAccounts {
+
+    /// The constructor for the ShardedAccounts struct.
+    ///
+    /// # Arguments
+    /// * shard_count (usize): the number of locks the accounts are split across, at least one is used
+    /// * config (EngineConfig): the policies applied to every transaction
+    ///
+    /// # Returns
+    /// * (ShardedAccounts): the empty shards
+    pub fn new(shard_count: usize, config: EngineConfig) -> ShardedAccounts {
+        let shards = (0..shard_count.max(1)).map(|_| Mutex::new(AccountMap::new().with_config(config.clone()))).collect();
+        return ShardedAccounts{shards, config}
+    }
+
+    /// Gets the shard a client's account is kept in.
+    ///
+    /// # Arguments
+    /// * client (ClientId): the ID of the client
+    ///
+    /// # Returns
+    /// * (&Mutex<AccountMap>): the shard of the client
+    fn shard_for(&self, client: ClientId) -> &Mutex<AccountMap> {
+        return &self.shards[(client.0 % self.shards.len() as u32) as usize]
+    }
+
+    /// Applies a transaction to the account of its client holding only the lock of the client's shard.
+    ///
+    /// # Arguments
+    /// * transaction (Transaction): the transaction to apply
+    ///
+    /// # Returns
+    /// * (Result<(), TransactionError>): the reason the transaction was rejected, it is also kept in the error log of the shard
+    pub fn apply(&self, transaction: Transaction) -> Result<(), TransactionError> {
+        let mut shard = self.shard_for(transaction.client).lock().unwrap_or_else(PoisonError::into_inner);
+        let rejected = shard.total_error_transaction_log.len();
+        // the map is rebuilt by add_transaction so it is swapped out while the transaction is applied
+        let account_map = mem::take(&mut *shard);
+        *shard = account_map.add_transaction(transaction);
+        match shard.total_error_transaction_log.get(rejected) {
+            Some((_, error)) => {return Err(error.clone())},
+            None => {return Ok(())}
+        }
+    }
+
+    /// Merges the shards into a single map of accounts once every thread has finished applying transactions.
+    ///
+    /// # Returns
+    /// * (AccountMap): the accounts and logs of every shard under the same config, the logs grouped by shard
+    pub fn into_account_map(self) -> AccountMap {
+        let mut account_map = AccountMap::new().with_config(self.config);
+        for shard in self.shards {
+            account_map.merge(shard.into_inner().unwrap_or_else(PoisonError::into_inner));
+        }
+        return account_map
+    }
+}
+
+
+#[cfg(test)]
+mod sharded_tests {
+
+    use std::thread;
+
+    use super::ShardedAccounts;
+    use super::super::super::engine::config::{DuplicatePolicy, EngineConfig};
+    use super::super::super::transactions::errors::TransactionError;
+    use super::super::super::transactions::transaction::Transaction;
+    use super::super::super::transactions::enums::TransactionType::{DEPOSIT, WITHDRAWAL};
+    use super::super::super::transactions::warnings::TransactionWarning;
+    use super::super::super::transactions::ids::{ClientId, TxId};
+
+    #[test]
+    fn test_concurrent_apply() {
+        let sharded = ShardedAccounts::new(3, EngineConfig::default());
+
+        thread::scope(|scope| {
+            for worker in 0..4 {
+                let sharded = &sharded;
+                scope.spawn(move || {
+                    // every thread has its own clients but they share shards with the other threads
+                    for client in (worker * 5)..(worker * 5 + 5) {
+                        for step in 0..20 {
+                            let tx = client * 100 + step;
+                            sharded.apply(Transaction::new(DEPOSIT, ClientId(client), TxId(tx), Some(1.0))).unwrap();
+                        }
+                        sharded.apply(Transaction::new(WITHDRAWAL, ClientId(client), TxId(client * 100 + 50), Some(5.0))).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(Err(TransactionError::InsufficientFunds), sharded.apply(Transaction::new(WITHDRAWAL, ClientId(0), TxId(99), Some(20.0))));
+        let account_map = sharded.into_account_map();
+        assert_eq!(20, account_map.accounts.len());
+        for account in account_map.accounts.values() {
+            assert_eq!(15.0, account.available());
+            assert_eq!(15.0, account.total());
+            assert_eq!(21, account.transaction_log.len());
+        }
+        assert_eq!(20 * 21, account_map.accepted_count);
+        assert_eq!(1, account_map.total_error_transaction_log.len());
+    }
+
+    #[test]
+    fn test_duplicate_rejected() {
+        let sharded = ShardedAccounts::new(2, EngineConfig::builder().default_deposit_amount(Some(1.0)).build());
+
+        sharded.apply(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(2.0))).unwrap();
+        assert_eq!(Err(TransactionError::DuplicateTxId), sharded.apply(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(3.0))));
+        // a deposit with no amount is given the default of the config the same as in the map
+        sharded.apply(Transaction::new(DEPOSIT, ClientId(2), TxId(2), None)).unwrap();
+
+        let account_map = sharded.into_account_map();
+        assert_eq!(2.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(1.0, account_map.accounts[&ClientId(2)].available());
+        assert_eq!(1, account_map.total_error_transaction_log.len());
+        assert_eq!(2, account_map.accepted_count);
+        assert_eq!(2, account_map.total_transaction_log.len());
+    }
+
+    #[test]
+    fn test_duplicate_dropped_warning() {
+        let sharded = ShardedAccounts::new(2, EngineConfig::builder().duplicate_policy(DuplicatePolicy::FirstWins).build());
+
+        sharded.apply(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(2.0))).unwrap();
+        sharded.apply(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(3.0))).unwrap();
+
+        let account_map = sharded.into_account_map();
+        assert_eq!(2.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(vec![TransactionWarning::DuplicateDropped], account_map.total_warning_log.iter().map(|(_, warning)| warning.clone()).collect::<Vec<TransactionWarning>>());
+    }
+}
@@ -0,0 +1,264 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use super::account::TransactionLog;
+use super::super::transactions::ids::TxId;
+use super::super::transactions::transaction::Transaction;
+use super::super::transactions::enums::TransactionType::{DEPOSIT, WITHDRAWAL};
+
+
+/// This trait is responsible for defining where the transactions of an account are kept so in memory and on disk logs can be swapped.
+pub trait TransactionStore {
+
+    /// Adds a transaction to the end of the store.
+    ///
+    /// # Arguments
+    /// * transaction (Transaction): the transaction to be kept
+    fn push(&mut self, transaction: Transaction);
+
+    /// Finds the deposit or withdrawal made under a transaction ID, disputes, resolves and chargebacks referring to it are skipped.
+    ///
+    /// # Arguments
+    /// * tx (TxId): the ID of the transaction
+    ///
+    /// # Returns
+    /// * (Option<&Transaction>): the deposit or withdrawal if it is in the store
+    fn find_by_tx(&self, tx: TxId) -> Option<&Transaction>;
+
+    /// Takes the deposit or withdrawal made under a transaction ID out of the store.
+    ///
+    /// # Arguments
+    /// * tx (TxId): the ID of the transaction
+    ///
+    /// # Returns
+    /// * (Option<Transaction>): the removed transaction, None if it is not in the store
+    fn remove_by_tx(&mut self, tx: TxId) -> Option<Transaction>;
+
+    /// Gets the transaction pushed last so it can be merged with the next one.
+    ///
+    /// # Returns
+    /// * (Option<&mut Transaction>): the last transaction, None if the store is empty
+    fn last_mut(&mut self) -> Option<&mut Transaction>;
+
+    /// Walks the transactions in the order they were pushed.
+    ///
+    /// # Returns
+    /// * (Box<dyn Iterator<Item = &Transaction> + '_>): the transactions in the store
+    fn iter(&self) -> Box<dyn Iterator<Item = &Transaction> + '_>;
+}
+
+
+/// Finds the deposit or withdrawal under a transaction ID in a run of transactions.
+///
+/// # Arguments
+/// * transactions (&[Transaction]): the transactions to search
+/// * tx (TxId): the ID of the transaction
+///
+/// # Returns
+/// * (Option<&Transaction>): the first deposit or withdrawal with the ID
+fn find_movement(transactions: &[Transaction], tx: TxId) -> Option<&Transaction> {
+    return transactions.get(movement_position(transactions, tx)?)
+}
+
+
+/// Finds where the deposit or withdrawal under a transaction ID is in a run of transactions.
+///
+/// # Arguments
+/// * transactions (&[Transaction]): the transactions to search
+/// * tx (TxId): the ID of the transaction
+///
+/// # Returns
+/// * (Option<usize>): the index of the first deposit or withdrawal with the ID
+fn movement_position(transactions: &[Transaction], tx: TxId) -> Option<usize> {
+    return transactions.iter().position(|logged| {
+        logged.tx == tx && (logged.transaction_type == DEPOSIT || logged.transaction_type == WITHDRAWAL)
+    })
+}
+
+
+/// This struct is responsible for keeping the transactions of an account in memory, it is the default store.
+///
+/// # Attributes
+/// * transactions (TransactionLog): the transactions in the order they were pushed
+#[derive(Debug, Clone, Default)]
+pub struct VecStore {
+    pub transactions: TransactionLog
+}
+
+impl VecStore {
+
+    /// The constructor for the VecStore struct.
+    ///
+    /// # Returns
+    /// * (VecStore): an empty store
+    pub fn new() -> VecStore {
+        return VecStore{transactions: TransactionLog::new()}
+    }
+}
+
+impl Deref for VecStore {
+
+    type Target = [Transaction];
+
+    fn deref(&self) -> &[Transaction] {
+        return &self.transactions
+    }
+}
+
+impl TransactionStore for VecStore {
+
+    fn push(&mut self, transaction: Transaction) {
+        self.transactions.push(transaction);
+    }
+
+    fn find_by_tx(&self, tx: TxId) -> Option<&Transaction> {
+        return find_movement(&self.transactions, tx)
+    }
+
+    fn remove_by_tx(&mut self, tx: TxId) -> Option<Transaction> {
+        let position = movement_position(&self.transactions, tx)?;
+        return Some(self.transactions.remove(position))
+    }
+
+    fn last_mut(&mut self) -> Option<&mut Transaction> {
+        return self.transactions.last_mut()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &Transaction> + '_> {
+        return Box::new(self.transactions.iter())
+    }
+}
+
+
+/// This struct is responsible for standing in for a store backed by a file, for now the transactions are only kept in memory
+/// and nothing is written to the path.
+///
+/// # Attributes
+/// * path (PathBuf): the file the transactions will be written to
+/// * transactions (Vec<Transaction>): the transactions in the order they were pushed
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: PathBuf,
+    transactions: Vec<Transaction>
+}
+
+impl FileStore {
+
+    /// The constructor for the FileStore struct.
+    ///
+    /// # Arguments
+    /// * path (&Path): the file the transactions will be written to
+    ///
+    /// # Returns
+    /// * (FileStore): an empty store for the path
+    pub fn new(path: &Path) -> FileStore {
+        return FileStore{path: path.to_path_buf(), transactions: Vec::new()}
+    }
+
+    /// Gets the file the transactions will be written to.
+    ///
+    /// # Returns
+    /// * (&Path): the path of the store
+    pub fn path(&self) -> &Path {
+        return &self.path
+    }
+}
+
+impl TransactionStore for FileStore {
+
+    fn push(&mut self, transaction: Transaction) {
+        self.transactions.push(transaction);
+    }
+
+    fn find_by_tx(&self, tx: TxId) -> Option<&Transaction> {
+        return find_movement(&self.transactions, tx)
+    }
+
+    fn remove_by_tx(&mut self, tx: TxId) -> Option<Transaction> {
+        let position = movement_position(&self.transactions, tx)?;
+        return Some(self.transactions.remove(position))
+    }
+
+    fn last_mut(&mut self) -> Option<&mut Transaction> {
+        return self.transactions.last_mut()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &Transaction> + '_> {
+        return Box::new(self.transactions.iter())
+    }
+}
+
+
+#[cfg(test)]
+mod store_tests {
+
+    use std::path::Path;
+
+    use super::{FileStore, TransactionStore, VecStore};
+    use super::super::account::Account;
+    use super::super::super::transactions::transaction::Transaction;
+    use super::super::super::transactions::enums::TransactionType::{DEPOSIT, DISPUTE, WITHDRAWAL};
+    use super::super::super::transactions::ids::{ClientId, TxId};
+
+    /// Fills any store with the same transactions so the implementations can be checked against each other.
+    fn fill<S: TransactionStore>(mut store: S) -> S {
+        store.push(Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)));
+        store.push(Transaction::new(DISPUTE,    ClientId(1), TxId(1), None));
+        store.push(Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(2.0)));
+        return store
+    }
+
+    #[test]
+    fn test_vec_store() {
+        let mut store = fill(VecStore::new());
+
+        assert_eq!(vec![TxId(1), TxId(1), TxId(2)], TransactionStore::iter(&store).map(|logged| logged.tx).collect::<Vec<TxId>>());
+        assert_eq!(DEPOSIT, store.find_by_tx(TxId(1)).unwrap().transaction_type);
+        assert_eq!(Some(2.0), store.find_by_tx(TxId(2)).unwrap().amount);
+        assert!(store.find_by_tx(TxId(3)).is_none());
+
+        assert_eq!(Some(WITHDRAWAL), store.remove_by_tx(TxId(2)).map(|removed| removed.transaction_type));
+        assert!(store.find_by_tx(TxId(2)).is_none());
+        assert_eq!(2, store.len());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let store = fill(FileStore::new(Path::new("transactions.log")));
+
+        assert_eq!(Path::new("transactions.log"), store.path());
+        assert_eq!(3, store.iter().count());
+        assert_eq!(DEPOSIT, store.find_by_tx(TxId(1)).unwrap().transaction_type);
+        assert!(store.find_by_tx(TxId(3)).is_none());
+    }
+
+    #[test]
+    fn test_dispute_lookup_through_store() {
+        let mut account = Account::new(ClientId(1));
+        account = account.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(5.0))).unwrap();
+        account = account.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(2), Some(3.0))).unwrap();
+        account = account.add_transaction(Transaction::new(DISPUTE, ClientId(1), TxId(2), None)).unwrap();
+
+        assert_eq!(Some(3.0), account.transaction_log.find_by_tx(TxId(2)).unwrap().amount);
+        assert_eq!(3.0, account.held());
+        assert_eq!(5.0, account.available());
+    }
+
+    #[test]
+    fn test_account_with_file_store() {
+        let mut account = Account::with_store(ClientId(1), FileStore::new(Path::new("transactions.log")));
+        account = account.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(1), Some(5.0))).unwrap();
+        account = account.add_transaction(Transaction::new(DEPOSIT, ClientId(1), TxId(2), Some(3.0))).unwrap();
+        account = account.add_transaction(Transaction::new(DISPUTE, ClientId(1), TxId(2), None)).unwrap();
+
+        // the dispute is looked up through the file store the same way as through the default one
+        assert_eq!(3, account.transaction_log.iter().count());
+        assert_eq!(3.0, account.held());
+        assert_eq!(5.0, account.available());
+
+        let account = account.reverse_transaction(TxId(1)).unwrap();
+        assert!(account.transaction_log.find_by_tx(TxId(1)).is_none());
+        assert_eq!(0.0, account.available());
+        assert_eq!(3.0, account.total());
+    }
+}
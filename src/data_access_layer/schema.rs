@@ -1,54 +1,86 @@
 use serde::{Deserialize, Serialize};
 
+use super::super::transactions::amount::FixedPoint;
 use super::super::transactions::enums::TransactionType;
+use super::super::transactions::ids::{ClientId, TxId};
 use super::super::transactions::transaction::Transaction;
-use super::super::accounts::account::Account;
+use super::super::accounts::account::{Account, AccountSnapshot};
+use super::super::accounts::store::TransactionStore;
+use super::super::engine::config::{Column, NegativeTotalOutput, OutputOptions};
+
+
+/// The input columns TransactionSchema reads, any other column is ignored unless the schema is strict.
+pub const KNOWN_COLUMNS: [&str; 7] = ["type", "transaction_type", "client", "tx", "amount", "timestamp", "currency"];
 
 
 /// This struct is responsible for Deserialising transactions from the CSV file. 
 /// 
 /// # Attributes 
 /// * transaction_type (String): the type of transaction (can be called "type" in the CSV)
-/// * client (i32): the ID of the user who is making the transaction 
-/// * tx (i32): the ID of the transaction 
-/// * amount (Option<f32>): the amount of the transaction
+/// * client (u32): the ID of the user who is making the transaction 
+/// * tx (u32): the ID of the transaction 
+/// * amount (Option<String>): the raw amount of the transaction, kept as text so it can be parsed without precision loss (None if the column is absent)
+/// * timestamp (Option<i64>): when the transaction happened, used to order the batch if enabled (None if the column is absent)
+/// * currency (Option<String>): the currency code of the amount, used to pick the scale it is parsed at (None if the column is absent)
 #[derive(Debug, Deserialize)]
 pub struct TransactionSchema {
     #[serde(alias = "type")]
     pub transaction_type: String,
-    pub client: i32,
-    pub tx: i32,
-    pub amount: Option<f32>
+    pub client: u32,
+    pub tx: u32,
+    #[serde(default)]
+    pub amount: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<i64>,
+    #[serde(default)]
+    pub currency: Option<String>
 }
 
 impl TransactionSchema {
 
     /// Concerts the struct into a Transaction struct. 
     /// 
+    /// # Arguments 
+    /// * scale (u32): the number of decimal places the amount is allowed to have
+    /// 
     /// # returns 
-    /// * (Transaction): the transaction struct fit for processing
-    pub fn convert_to_transaction(self) -> Transaction {
-        let transaction_type = TransactionType::new(self.transaction_type);
-        return Transaction{transaction_type, client: self.client, tx: self.tx, amount: self.amount}
+    /// * (Result<Transaction, String>): the transaction struct fit for processing, or an error if the type is unknown or the amount cannot be represented exactly
+    pub fn convert_to_transaction(self, scale: u32) -> Result<Transaction, String> {
+        let transaction_type = TransactionType::new(self.transaction_type)?;
+        let exact_amount = match self.amount {
+            Some(raw_amount) => Some(FixedPoint::parse(&raw_amount, scale)?),
+            None => None
+        };
+        let amount = exact_amount.map(|exact| exact.to_f32());
+        return Ok(Transaction{transaction_type, client: ClientId(self.client), tx: TxId(self.tx), amount, exact_amount})
     }
 }
 
 
-/// This struct is responsible for serialising account data to be written to a CSV file. 
+/// This struct is responsible for serialising account data to be written to a CSV file and reading it back to resume from. 
 /// 
 /// # Attributes 
-/// * client (i32): the ID of the cient and thus the account 
+/// * client (u32): the ID of the cient and thus the account 
 /// * available (f32): the amount of funds available 
 /// * held (f32): the amount of funds held 
 /// * total (f32): the total amount of funds 
 /// * locked (bool): if the account is locked or not 
-#[derive(Debug, Serialize)]
+/// * tx_count (Option<usize>): the number of accepted transactions, the column is only written when set
+/// * locked_by_tx (Option<u32>): the transaction that locked the account, blank in the locked_by_tx column if it is open
+/// * scale (Option<u32>): the decimal places the amounts are written with when the account's currency has its own scale, never a column
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AccountSchema {
-    pub client: i32,
+    pub client: u32,
     pub available: f32,
     pub held: f32,
     pub total: f32,
-    pub locked: bool
+    pub locked: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tx_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub locked_by_tx: Option<u32>,
+    #[serde(skip)]
+    pub scale: Option<u32>
 }
 
 impl AccountSchema {
@@ -56,12 +88,175 @@ impl AccountSchema {
     /// Gets data from account that has been processed to be ready to be written. 
     /// 
     /// # Arguments 
-    /// * account (Account): the account to be serialised
+    /// * account (&Account<S>): the account to be serialised, whichever store it keeps its transactions in
+    /// 
+    /// # Returns 
+    /// (AccountSchema): the schema to be written to CSV 
+    pub fn convert_from_account<S: TransactionStore>(account: &Account<S>) -> AccountSchema {
+        let mut schema = AccountSchema::from_snapshot(&account.snapshot());
+        schema.scale = account.currency_scale();
+        return schema
+    }
+
+    /// Builds the row for an account from a snapshot of its balances so the full account is not needed. 
+    /// 
+    /// # Arguments 
+    /// * snapshot (&AccountSnapshot): the balances to be serialised
     /// 
     /// # Returns 
     /// (AccountSchema): the schema to be written to CSV 
-    pub fn convert_from_account(account: Account) -> AccountSchema {
-        return AccountSchema{client: account.id, available: account.amount_available, held: account.amount_held, total: account.total, locked: account.locked}
+    pub fn from_snapshot(snapshot: &AccountSnapshot) -> AccountSchema {
+        return AccountSchema{client: snapshot.client.0, available: snapshot.available, held: snapshot.held, total: snapshot.total, locked: snapshot.locked, tx_count: None, locked_by_tx: snapshot.locked_by_tx.map(|tx| tx.0), scale: None}
     }
 
+    /// Gets the names of the columns written for the options. 
+    /// 
+    /// # Arguments 
+    /// * options (&OutputOptions): the options deciding the columns and their order
+    /// 
+    /// # Returns 
+    /// (Vec<&'static str>): the header row
+    pub fn header(options: &OutputOptions) -> Vec<&'static str> {
+        return options.columns().iter().map(|column| column.name()).collect()
+    }
+
+    /// Formats the schema as a row of CSV fields matching the header for the same options. 
+    /// 
+    /// # Arguments 
+    /// * options (&OutputOptions): the options deciding the columns and number format
+    /// 
+    /// # Returns 
+    /// (Vec<String>): the fields of the row
+    pub fn to_record(&self, options: &OutputOptions) -> Vec<String> {
+        return options.columns().iter().map(|column| {
+            match column {
+                Column::Client => self.client.to_string(),
+                Column::Available => self.format(self.available, options),
+                Column::Held => self.format(self.held, options),
+                Column::Total if options.negative_total == NegativeTotalOutput::Clamp => self.format(self.total.max(0.0), options),
+                Column::Total => self.format(self.total, options),
+                Column::Locked => self.locked.to_string(),
+                Column::TxCount => self.tx_count.unwrap_or(0).to_string(),
+                Column::LockedByTx => self.locked_by_tx.map(|tx| tx.to_string()).unwrap_or_default(),
+                Column::Overdrawn => (self.total < 0.0).to_string()
+            }
+        }).collect()
+    }
+
+    /// Formats an amount of the account, a fixed scale in the options wins over the scale of its currency. 
+    /// 
+    /// # Arguments 
+    /// * value (f32): the amount to format
+    /// * options (&OutputOptions): the options holding the fixed scale and decimal separator
+    /// 
+    /// # Returns 
+    /// (String): the formatted amount
+    fn format(&self, value: f32, options: &OutputOptions) -> String {
+        return format_amount_at(value, options.fixed_scale.or(self.scale), options)
+    }
+}
+
+
+/// Formats an amount the same way the CSV serialiser writes an f32, swapping the decimal separator if configured. 
+/// 
+/// # Arguments 
+/// * value (f32): the amount to format
+/// * options (&OutputOptions): the options holding the decimal separator
+/// 
+/// # Returns 
+/// (String): the formatted amount
+pub fn format_amount(value: f32, options: &OutputOptions) -> String {
+    return format_amount_at(value, options.fixed_scale, options)
+}
+
+
+/// Formats an amount at a number of decimal places, or the same way the CSV serialiser writes an f32 without one. 
+/// 
+/// # Arguments 
+/// * value (f32): the amount to format
+/// * scale (Option<u32>): the decimal places written, None for the shortest form that reads back as the same f32
+/// * options (&OutputOptions): the options holding the decimal separator
+/// 
+/// # Returns 
+/// (String): the formatted amount
+pub fn format_amount_at(value: f32, scale: Option<u32>, options: &OutputOptions) -> String {
+    let mut buffer = ryu::Buffer::new();
+    let formatted = match scale {
+        Some(scale) => format_plain(value, scale),
+        None => buffer.format(value).to_string()
+    };
+    if options.decimal_separator == '.' {
+        return formatted
+    }
+    return formatted.replace('.', &options.decimal_separator.to_string())
+}
+
+
+/// Formats an amount as a plain decimal with a fixed number of decimal places so tiny float residuals never come out in scientific notation. 
+/// 
+/// # Arguments 
+/// * value (f32): the amount to format
+/// * scale (u32): the number of decimal places written
+/// 
+/// # Returns 
+/// (String): the formatted amount, a residual that rounds to zero is written without a sign
+pub fn format_plain(value: f32, scale: u32) -> String {
+    let formatted = format!("{:.*}", scale as usize, value);
+    match formatted.strip_prefix('-') {
+        Some(unsigned) if unsigned.chars().all(|c| c == '0' || c == '.') => {return unsigned.to_string()},
+        _ => {return formatted}
+    }
+}
+
+
+#[cfg(test)]
+mod schema_tests {
+
+    use super::{format_amount, format_plain, AccountSchema, TransactionSchema};
+    use super::super::super::transactions::amount::FixedPoint;
+    use super::super::super::engine::config::OutputOptions;
+    use super::super::super::accounts::account::{Account, AccountSnapshot};
+    use super::super::super::transactions::transaction::Transaction;
+    use super::super::super::transactions::enums::TransactionType::{DEPOSIT, DISPUTE};
+    use super::super::super::transactions::ids::{ClientId, TxId};
+
+    #[test]
+    fn test_from_snapshot() {
+        let snapshot = AccountSnapshot{client: ClientId(4), available: 1.5, held: 2.0, total: 3.5, locked: true, locked_by_tx: Some(TxId(9))};
+        let schema = AccountSchema::from_snapshot(&snapshot);
+
+        assert_eq!((4, 1.5, 2.0, 3.5, true, None, Some(9)), (schema.client, schema.available, schema.held, schema.total, schema.locked, schema.tx_count, schema.locked_by_tx));
+    }
+
+    #[test]
+    fn test_snapshot_of_account() {
+        let mut account = Account::new(ClientId(2));
+        account = account.add_transaction(Transaction::new(DEPOSIT, ClientId(2), TxId(1), Some(3.0))).unwrap();
+        account = account.add_transaction(Transaction::new(DISPUTE, ClientId(2), TxId(1), None)).unwrap();
+
+        let schema = AccountSchema::from_snapshot(&account.snapshot());
+        assert_eq!((2, 0.0, 3.0, 3.0, false), (schema.client, schema.available, schema.held, schema.total, schema.locked));
+    }
+
+    #[test]
+    fn test_convert_keeps_exact_amount() {
+        let raw = TransactionSchema{transaction_type: "deposit".to_string(), client: 1, tx: 1, amount: Some("0.1".to_string()), timestamp: None, currency: None};
+        let transaction = raw.convert_to_transaction(4).unwrap();
+
+        // 0.1 has no exact f32 so only the units show it was kept exactly
+        assert_eq!(Some(FixedPoint{units: 1000, scale: 4}), transaction.exact_amount);
+    }
+
+    #[test]
+    fn test_format_plain() {
+        let residual: f32 = 0.0000001;
+        let fixed = OutputOptions{fixed_scale: Some(4), ..OutputOptions::default()};
+
+        assert_eq!("1e-7", format_amount(residual, &OutputOptions::default()));
+        assert_eq!("0.0000", format_amount(residual, &fixed));
+        assert_eq!("0.0000", format_plain(-residual, 4));
+        assert_eq!("-1.5000", format_plain(-1.5, 4));
+        assert_eq!("12.35", format_plain(12.345678, 2));
+        assert_eq!("3,1000", format_amount(3.1, &OutputOptions{decimal_separator: ',', delimiter: b';', ..fixed}));
+    }
 }
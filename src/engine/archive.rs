@@ -0,0 +1,73 @@
+use std::io::{Error, Read, Seek};
+use std::sync::atomic::AtomicBool;
+
+use zip::ZipArchive;
+
+use super::config::EngineConfig;
+use super::errors::EngineError;
+use super::process_records;
+use super::super::accounts::account_map::AccountMap;
+
+
+/// Reads every `.csv` entry of a zip archive in name order as one stream of transactions, so a dispute in one entry
+/// finds a deposit from an earlier one. Each entry has its own header row, other entries such as directories are skipped
+/// and with sort_by_timestamp the rows are sorted within each entry.
+///
+/// # Arguments
+/// * archive (R): the zip archive
+/// * config (&EngineConfig): the parsing options and the policies applied to the transactions
+///
+/// # Returns
+/// * (Result<AccountMap, EngineError>): the accounts after every entry, or an error if the archive or an entry could not be read
+pub fn process_zip<R: Read + Seek>(archive: R, config: &EngineConfig) -> Result<AccountMap, EngineError> {
+    let mut archive = ZipArchive::new(archive).map_err(Error::other)?;
+    let names = archive.file_names().map(|name| name.map(|name| name.into_owned())).collect::<Result<Vec<String>, _>>().map_err(Error::other)?;
+    let mut names: Vec<String> = names.into_iter().filter(|name| name.ends_with(".csv")).collect();
+    names.sort();
+
+    let mut account_map = AccountMap::new().with_config(config.clone());
+    let stop = AtomicBool::new(false);
+    for name in names {
+        let entry = archive.by_name(&name).map_err(Error::other)?;
+        account_map = process_records(account_map, entry, config, &stop, None, None)?;
+    }
+    return Ok(account_map)
+}
+
+
+#[cfg(test)]
+mod archive_tests {
+
+    use std::io::{Cursor, Write};
+
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use super::process_zip;
+    use super::super::config::EngineConfig;
+    use super::super::super::transactions::ids::ClientId;
+
+    #[test]
+    fn test_process_zip() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        // the entries are written out of order to check they are read by name
+        let entries = vec![
+            ("02.csv", "type,client,tx,amount\ndispute,1,1,\nresolve,1,1,\ndispute,1,2,\n"),
+            ("notes.txt", "not transactions"),
+            ("01.csv", "type,client,tx,amount\ndeposit,1,1,5.0\ndeposit,1,2,3.0\n")
+        ];
+        for (name, contents) in entries {
+            writer.start_file(name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let archive = writer.finish().unwrap();
+
+        let account_map = process_zip(archive, &EngineConfig::default()).unwrap();
+
+        let account = account_map.accounts.get(&ClientId(1)).unwrap();
+        assert_eq!(5.0, account.available());
+        assert_eq!(3.0, account.held());
+        assert_eq!(8.0, account.total());
+        assert_eq!(5, account_map.accepted_count);
+    }
+}
@@ -0,0 +1,112 @@
+use std::io::{Error, Write};
+use std::sync::Arc;
+
+use parquet::data_type::{BoolType, FloatType, Int32Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+
+use super::errors::EngineError;
+use super::super::accounts::account_map::AccountMap;
+use super::super::data_access_layer::schema::AccountSchema;
+
+
+/// The Parquet schema of the accounts, in the same order as the default CSV columns.
+const ACCOUNT_SCHEMA: &str = "
+    message account {
+        REQUIRED INT32 client (INTEGER(32, false));
+        REQUIRED FLOAT available;
+        REQUIRED FLOAT held;
+        REQUIRED FLOAT total;
+        REQUIRED BOOLEAN locked;
+    }
+";
+
+
+/// Writes the state of every account as a Parquet file with typed columns, sorted by client like the CSV output.
+///
+/// # Arguments
+/// * account_map (&AccountMap): the processed accounts to be written
+/// * output (W): where the Parquet file is written to
+///
+/// # Returns
+/// * (Result<(), EngineError>): an error if the file could not be written
+pub fn write_parquet<W: Write + Send>(account_map: &AccountMap, output: W) -> Result<(), EngineError> {
+    let schemas: Vec<AccountSchema> = account_map.to_schemas();
+    let schema = Arc::new(parse_message_type(ACCOUNT_SCHEMA).map_err(Error::other)?);
+    let mut writer = SerializedFileWriter::new(output, schema, Arc::new(WriterProperties::builder().build())).map_err(Error::other)?;
+
+    // unsigned columns are written through the signed physical type with the same bits
+    let clients: Vec<i32> = schemas.iter().map(|schema| schema.client as i32).collect();
+    let available: Vec<f32> = schemas.iter().map(|schema| schema.available).collect();
+    let held: Vec<f32> = schemas.iter().map(|schema| schema.held).collect();
+    let total: Vec<f32> = schemas.iter().map(|schema| schema.total).collect();
+    let locked: Vec<bool> = schemas.iter().map(|schema| schema.locked).collect();
+
+    let mut row_group = writer.next_row_group().map_err(Error::other)?;
+    let mut column_index = 0;
+    while let Some(mut column) = row_group.next_column().map_err(Error::other)? {
+        let written = match column_index {
+            0 => column.typed::<Int32Type>().write_batch(&clients, None, None),
+            1 => column.typed::<FloatType>().write_batch(&available, None, None),
+            2 => column.typed::<FloatType>().write_batch(&held, None, None),
+            3 => column.typed::<FloatType>().write_batch(&total, None, None),
+            _ => column.typed::<BoolType>().write_batch(&locked, None, None)
+        };
+        written.map_err(Error::other)?;
+        column.close().map_err(Error::other)?;
+        column_index += 1;
+    }
+    row_group.close().map_err(Error::other)?;
+    writer.close().map_err(Error::other)?;
+    return Ok(())
+}
+
+
+#[cfg(test)]
+mod columnar_tests {
+
+    use std::fs::File;
+
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+
+    use super::write_parquet;
+    use super::super::super::accounts::account_map::AccountMap;
+    use super::super::super::transactions::transaction::Transaction;
+    use super::super::super::transactions::enums::TransactionType::{CHARGEBACK, DEPOSIT, DISPUTE, WITHDRAWAL};
+    use super::super::super::transactions::ids::{ClientId, TxId};
+
+    #[test]
+    fn test_write_parquet() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(3), TxId(1), Some(2.5)),
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(2), Some(4.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(1.5)),
+            Transaction::new(DISPUTE,    ClientId(3), TxId(1), None),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(4), Some(1.0)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(4), None),
+            Transaction::new(CHARGEBACK, ClientId(2), TxId(4), None)
+        ];
+        let mut account_map = AccountMap::new();
+        for transaction in transactions {
+            account_map = account_map.add_transaction(transaction);
+        }
+        let path = std::env::temp_dir().join(format!("accounts_{}.parquet", std::process::id()));
+
+        write_parquet(&account_map, File::create(&path).unwrap()).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let rows: Vec<(u32, f32, f32, f32, bool)> = reader.get_row_iter(None).unwrap().map(|row| {
+            let row = row.unwrap();
+            return (row.get_uint(0).unwrap(), row.get_float(1).unwrap(), row.get_float(2).unwrap(), row.get_float(3).unwrap(), row.get_bool(4).unwrap())
+        }).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(vec![
+            (1, 2.5, 0.0, 2.5, false),
+            (2, 0.0, 0.0, 0.0, true),
+            (3, 0.0, 2.5, 2.5, false)
+        ], rows);
+    }
+}
@@ -0,0 +1,1069 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::super::transactions::amount::{AmountFormat, DEFAULT_EPSILON, DEFAULT_SCALE};
+use super::super::transactions::ids::ClientId;
+
+
+/// This enum is responsible for defining what happens when a row of the input cannot be parsed.
+///
+/// # Attributes
+/// * Continue: the bad row is recorded in the skipped rows of the map and processing carries on with the next row
+/// * Fail: processing stops at the first bad row
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum OnError {
+    #[default]
+    Continue,
+    Fail
+}
+
+impl FromStr for OnError {
+    type Err = String;
+
+    fn from_str(selection: &str) -> Result<Self, Self::Err> {
+        match selection {
+            "continue" => {return Ok(OnError::Continue)},
+            "fail" => {return Ok(OnError::Fail)},
+            _ => {return Err(format!("on-error mode '{}' is not supported, expected continue or fail", selection))}
+        }
+    }
+}
+
+
+/// This enum is responsible for defining what happens to a dispute made on an account that has been locked.
+///
+/// # Attributes
+/// * Reject: the dispute is rejected like any other transaction on a locked account
+/// * Note: a dispute on a transaction made before the lock is recorded on the account without moving any funds
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum LockedDisputePolicy {
+    #[default]
+    Reject,
+    Note
+}
+
+
+/// This enum is responsible for defining what happens when a chargeback leaves the total of an account below zero.
+///
+/// # Attributes
+/// * Allow: the negative total is kept as the spec describes
+/// * Clamp: the total is set to zero and the amount removed is recorded on the account as a discrepancy
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum NegativeTotalPolicy {
+    #[default]
+    Allow,
+    Clamp
+}
+
+
+/// This enum is responsible for defining where deposit and withdrawal transaction IDs have to be unique.
+///
+/// # Attributes
+/// * Global: a transaction ID can only be used by one deposit or withdrawal in the whole stream
+/// * PerClient: a transaction ID can only be used by one deposit or withdrawal of each client
+/// * Unchecked: transaction IDs are not checked
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum TxIdScope {
+    Global,
+    #[default]
+    PerClient,
+    Unchecked
+}
+
+
+/// This enum is responsible for defining what happens to a deposit or withdrawal that reuses a transaction ID.
+///
+/// # Attributes
+/// * Reject: the later transaction is rejected
+/// * FirstWins: the earlier transaction is kept and the later one is dropped, recorded as DuplicateDropped in the warning log of the map
+/// * LastWins: the earlier transaction is undone and replaced by the later one, unless it is disputed or its account is locked in which case the later one is rejected
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum DuplicatePolicy {
+    #[default]
+    Reject,
+    FirstWins,
+    LastWins
+}
+
+
+/// This enum is responsible for defining what happens when a dispute, resolve or chargeback carries an amount that disagrees with the amount it refers to.
+///
+/// # Attributes
+/// * Ignore: the amount on the row is ignored and the amount it refers to is used
+/// * Warn: the amount it refers to is used and an AmountMismatch warning is recorded on the account and in the warning log of the map
+/// * Reject: the dispute, resolve or chargeback is rejected
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum AmountMismatchPolicy {
+    #[default]
+    Ignore,
+    Warn,
+    Reject
+}
+
+
+/// This enum is responsible for defining what happens to a deposit or withdrawal too large for f32 to hold exactly.
+///
+/// # Attributes
+/// * Ignore: the amount is applied as it was parsed
+/// * Warn: the amount is applied and a warning is added to the warning log
+/// * Reject: the deposit or withdrawal is rejected
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum LargeAmountPolicy {
+    #[default]
+    Ignore,
+    Warn,
+    Reject
+}
+
+
+/// This enum is responsible for defining where the funds of a resolved dispute go.
+///
+/// # Attributes
+/// * Available: the held funds are released back to available as the spec describes
+/// * StayHeld: the dispute is closed but the funds stay held under a new hold on the account
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ResolveTarget {
+    #[default]
+    Available,
+    StayHeld
+}
+
+
+/// This enum is responsible for defining the arithmetic the balances of an account are kept in.
+///
+/// # Attributes
+/// * Float: the balances are f32 sums, the fastest but small errors build up over many transactions
+/// * Decimal: the balances are sums of whole units at the scale of the account and the f32 balances follow them, only with the decimal feature
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ArithmeticBackend {
+    #[default]
+    Float,
+    #[cfg(feature = "decimal")]
+    Decimal
+}
+
+impl ArithmeticBackend {
+
+    /// Checks if the backend keeps the balances as whole units.
+    ///
+    /// # Returns
+    /// * (bool): true for the decimal backend
+    pub fn is_exact(&self) -> bool {
+        match self {
+            ArithmeticBackend::Float => {return false},
+            #[cfg(feature = "decimal")]
+            ArithmeticBackend::Decimal => {return true}
+        }
+    }
+}
+
+
+/// This enum is responsible for defining what happens to the other open disputes of an account when a chargeback locks it.
+///
+/// # Attributes
+/// * LeaveHeld: the funds of the other disputes stay held as they were
+/// * Resolve: the other disputes are resolved as if a resolve had been sent for each of them
+/// * Chargeback: the other disputes are charged back along with the one that locked the account
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum LockHoldPolicy {
+    #[default]
+    LeaveHeld,
+    Resolve,
+    Chargeback
+}
+
+
+/// This enum is responsible for defining how the funds move when a withdrawal is disputed.
+///
+/// # Attributes
+/// * HoldReturnedFunds: the withdrawn amount is provisionally returned to the account as held funds, raising held and total.
+///   A resolve removes it again as the withdrawal stands, a chargeback releases it to available as the withdrawal is reversed.
+/// * HoldOriginalDebit: the withdrawal stays debited and the same amount of available funds is held, leaving total unchanged.
+///   A resolve releases the hold, a chargeback releases the hold and credits the withdrawn amount back to available.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WithdrawalDisputeMode {
+    HoldReturnedFunds,
+    HoldOriginalDebit
+}
+
+
+/// This enum is responsible for defining which clients have their transactions processed, the others are skipped without being logged.
+///
+/// # Attributes
+/// * Include: only the listed clients are processed
+/// * Exclude: every client but the listed ones is processed
+#[derive(Debug, PartialEq, Clone)]
+pub enum ClientFilter {
+    Include(HashSet<ClientId>),
+    Exclude(HashSet<ClientId>)
+}
+
+impl ClientFilter {
+
+    /// Checks if the transactions of a client pass the filter.
+    ///
+    /// # Arguments
+    /// * client (ClientId): the ID of the client
+    ///
+    /// # Returns
+    /// * (bool): true if the client's transactions should be processed
+    pub fn allows(&self, client: ClientId) -> bool {
+        match self {
+            ClientFilter::Include(clients) => {return clients.contains(&client)},
+            ClientFilter::Exclude(clients) => {return !clients.contains(&client)}
+        }
+    }
+}
+
+
+/// This struct is responsible for holding the callback fired with the number of rows read, shared so the config can still be cloned.
+#[derive(Clone)]
+pub struct ProgressCallback(pub Arc<dyn Fn(usize) + Send + Sync>);
+
+impl fmt::Debug for ProgressCallback {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "ProgressCallback")
+    }
+}
+
+
+/// This struct is responsible for housing the rules the engine applies when processing transactions.
+///
+/// # Attributes
+/// * on_error (OnError): whether a row that cannot be parsed is skipped or stops processing
+/// * sort_by_timestamp (bool): if true the whole batch is ordered by the timestamp column before processing (rows without one come first)
+/// * locked_dispute_policy (LockedDisputePolicy): how disputes on locked accounts are handled
+/// * recompute_total (bool): if true total is derived as available + held after every transaction instead of being updated on its own
+/// * max_balance (Option<f32>): the most an account's total may hold after a deposit, None for unlimited
+/// * redispute_allowed (bool): if false a transaction that has been resolved can not be disputed again
+/// * negative_total_policy (NegativeTotalPolicy): how a negative total after a chargeback is handled
+/// * max_open_disputes (Option<usize>): the most disputes a client may have open at once, None for unlimited
+/// * currency_scales (HashMap<String, u32>): the number of decimal places amounts in each currency are parsed at, currencies not listed use the default scale
+/// * tx_id_scope (TxIdScope): where deposit and withdrawal transaction IDs have to be unique, a reused ID is rejected
+/// * progress_interval (Option<usize>): if set the row count is passed to progress_callback every time this many rows have been read, None for no progress
+/// * progress_callback (Option<ProgressCallback>): receives the number of rows read at every progress_interval, None for no progress
+/// * flexible (bool): if true rows may have fewer fields than the header, the missing trailing fields being empty, or more, the extra fields being ignored
+/// * duplicate_policy (DuplicatePolicy): what happens to a deposit or withdrawal that reuses a transaction ID within tx_id_scope
+/// * resolve_target (ResolveTarget): where the funds of a resolved dispute go
+/// * withdrawal_dispute_mode (Option<WithdrawalDisputeMode>): how funds move when a withdrawal is disputed, None to ignore disputes on withdrawals as the spec only describes deposits
+/// * allow_comments (bool): if true lines starting with # are skipped as comments
+/// * epsilon (f32): float balances closer than this are treated as equal, so a withdrawal that float rounding has made a fraction larger than the available funds still goes through
+/// * max_accounts (Option<usize>): the most accounts processing may create before it stops with an error, None for unlimited
+/// * default_deposit_amount (Option<f32>): the amount given to a deposit that has none, None to reject such deposits
+/// * amount_mismatch_policy (AmountMismatchPolicy): what happens when a resolve or chargeback carries an amount that disagrees with the held amount
+/// * round_each_op (bool): if true the balances are rounded to the scale of the account currency after every transaction so float dust does not build up between comparisons
+/// * locked_accepts_deposits (bool): if true deposits are still accepted by a locked account, everything else is still rejected
+/// * input_delimiter (u8): the character separating the input fields
+/// * has_headers (bool): if false the input has no header row and its columns are taken to be type, client, tx and amount
+/// * auto_detect (bool): if true the delimiter and header row are guessed from the start of the input instead of using input_delimiter and has_headers
+/// * strict_schema (bool): if true an input header with columns outside the known set stops processing
+/// * negative_deposit_as_withdrawal (bool): if true a deposit with a negative amount is applied as a withdrawal of its magnitude instead of being rejected
+/// * worker_threads (usize): the number of worker threads process_parallel partitions the clients across
+/// * worker_queue_bound (usize): the most transactions waiting for each worker before the reader blocks
+/// * amount_format (Option<AmountFormat>): if set amounts are stripped of the currency symbol and thousands separators of the format before they are parsed
+/// * no_log (bool): if true balances are kept but no transaction is logged, so disputes, resolves and chargebacks are rejected and reused transaction IDs are not detected
+/// * client_filter (Option<ClientFilter>): the clients whose transactions are processed or skipped, None processes every client
+/// * coalesce_deposits (bool): if true consecutive deposits of an account are kept as one log entry with their sum, which turns disputes off
+/// * tx_namespace_per_file (bool): if true process_files gives each input file its own transaction IDs so the same ID in two files is two transactions
+/// * large_amount_policy (LargeAmountPolicy): what happens to a deposit or withdrawal, or a deposit that leaves a balance, too large for f32 to hold every unit at the scale of the account exactly
+/// * dispute_amount_policy (AmountMismatchPolicy): what happens when a dispute carries an amount that disagrees with the transaction it disputes, by default the amount is ignored
+/// * lock_hold_policy (LockHoldPolicy): what happens to the other open disputes when a chargeback locks the account
+/// * arithmetic (ArithmeticBackend): the arithmetic the balances are kept in, f32 by default or whole units with the decimal feature
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub on_error: OnError,
+    pub sort_by_timestamp: bool,
+    pub locked_dispute_policy: LockedDisputePolicy,
+    pub recompute_total: bool,
+    pub max_balance: Option<f32>,
+    pub redispute_allowed: bool,
+    pub negative_total_policy: NegativeTotalPolicy,
+    pub max_open_disputes: Option<usize>,
+    pub currency_scales: HashMap<String, u32>,
+    pub tx_id_scope: TxIdScope,
+    pub progress_interval: Option<usize>,
+    pub progress_callback: Option<ProgressCallback>,
+    pub flexible: bool,
+    pub duplicate_policy: DuplicatePolicy,
+    pub resolve_target: ResolveTarget,
+    pub withdrawal_dispute_mode: Option<WithdrawalDisputeMode>,
+    pub allow_comments: bool,
+    pub epsilon: f32,
+    pub max_accounts: Option<usize>,
+    pub default_deposit_amount: Option<f32>,
+    pub amount_mismatch_policy: AmountMismatchPolicy,
+    pub round_each_op: bool,
+    pub locked_accepts_deposits: bool,
+    pub input_delimiter: u8,
+    pub has_headers: bool,
+    pub auto_detect: bool,
+    pub strict_schema: bool,
+    pub negative_deposit_as_withdrawal: bool,
+    pub worker_threads: usize,
+    pub worker_queue_bound: usize,
+    pub amount_format: Option<AmountFormat>,
+    pub no_log: bool,
+    pub client_filter: Option<ClientFilter>,
+    pub coalesce_deposits: bool,
+    pub tx_namespace_per_file: bool,
+    pub large_amount_policy: LargeAmountPolicy,
+    pub dispute_amount_policy: AmountMismatchPolicy,
+    pub lock_hold_policy: LockHoldPolicy,
+    pub arithmetic: ArithmeticBackend
+}
+
+impl Default for EngineConfig {
+
+    fn default() -> Self {
+        return EngineConfig{
+            on_error: OnError::default(),
+            sort_by_timestamp: false,
+            locked_dispute_policy: LockedDisputePolicy::default(),
+            recompute_total: false,
+            max_balance: None,
+            redispute_allowed: true,
+            negative_total_policy: NegativeTotalPolicy::default(),
+            max_open_disputes: None,
+            currency_scales: HashMap::new(),
+            tx_id_scope: TxIdScope::PerClient,
+            progress_interval: None,
+            progress_callback: None,
+            flexible: false,
+            duplicate_policy: DuplicatePolicy::Reject,
+            resolve_target: ResolveTarget::Available,
+            withdrawal_dispute_mode: None,
+            allow_comments: false,
+            epsilon: DEFAULT_EPSILON,
+            max_accounts: None,
+            default_deposit_amount: None,
+            amount_mismatch_policy: AmountMismatchPolicy::Ignore,
+            round_each_op: false,
+            locked_accepts_deposits: false,
+            input_delimiter: b',',
+            has_headers: true,
+            auto_detect: false,
+            strict_schema: false,
+            negative_deposit_as_withdrawal: false,
+            worker_threads: 4,
+            worker_queue_bound: 1024,
+            amount_format: None,
+            no_log: false,
+            client_filter: None,
+            coalesce_deposits: false,
+            tx_namespace_per_file: false,
+            large_amount_policy: LargeAmountPolicy::Ignore,
+            dispute_amount_policy: AmountMismatchPolicy::Ignore,
+            lock_hold_policy: LockHoldPolicy::LeaveHeld,
+            arithmetic: ArithmeticBackend::default()
+        }
+    }
+}
+
+impl EngineConfig {
+
+    /// Starts building a config from the defaults.
+    ///
+    /// # Returns
+    /// * (EngineConfigBuilder): the builder with every option at its default
+    pub fn builder() -> EngineConfigBuilder {
+        return EngineConfigBuilder{config: EngineConfig::default()}
+    }
+
+    /// Gets the number of decimal places amounts of a currency are parsed at.
+    ///
+    /// # Arguments
+    /// * currency (Option<&str>): the currency code of the transaction, None if the input has no currency
+    ///
+    /// # Returns
+    /// * (u32): the configured scale of the currency, or the default scale if it has none
+    pub fn scale_for(&self, currency: Option<&str>) -> u32 {
+        return currency.and_then(|code| self.currency_scales.get(code).copied()).unwrap_or(DEFAULT_SCALE)
+    }
+}
+
+
+/// This struct is responsible for building an EngineConfig one option at a time.
+///
+/// # Attributes
+/// * config (EngineConfig): the config being built
+#[derive(Debug, Clone)]
+pub struct EngineConfigBuilder {
+    config: EngineConfig
+}
+
+impl EngineConfigBuilder {
+
+    /// Sets whether a row that cannot be parsed is skipped or stops processing.
+    ///
+    /// # Arguments
+    /// * on_error (OnError): the mode to apply
+    ///
+    /// # Returns
+    /// * (Self): the builder with the mode set
+    pub fn on_error(mut self, on_error: OnError) -> Self {
+        self.config.on_error = on_error;
+        return self
+    }
+
+    /// Sets whether the batch is ordered by timestamp before processing.
+    ///
+    /// # Arguments
+    /// * sort_by_timestamp (bool): true to sort the batch
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn sort_by_timestamp(mut self, sort_by_timestamp: bool) -> Self {
+        self.config.sort_by_timestamp = sort_by_timestamp;
+        return self
+    }
+
+    /// Sets how disputes on locked accounts are handled.
+    ///
+    /// # Arguments
+    /// * policy (LockedDisputePolicy): the policy to apply
+    ///
+    /// # Returns
+    /// * (Self): the builder with the policy set
+    pub fn locked_dispute_policy(mut self, policy: LockedDisputePolicy) -> Self {
+        self.config.locked_dispute_policy = policy;
+        return self
+    }
+
+    /// Sets whether total is derived from available and held after every transaction.
+    ///
+    /// # Arguments
+    /// * recompute_total (bool): true to derive the total
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn recompute_total(mut self, recompute_total: bool) -> Self {
+        self.config.recompute_total = recompute_total;
+        return self
+    }
+
+    /// Sets the most an account's total may hold after a deposit.
+    ///
+    /// # Arguments
+    /// * max_balance (Option<f32>): the cap, None for unlimited
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn max_balance(mut self, max_balance: Option<f32>) -> Self {
+        self.config.max_balance = max_balance;
+        return self
+    }
+
+    /// Sets whether a resolved transaction can be disputed again.
+    ///
+    /// # Arguments
+    /// * redispute_allowed (bool): false to reject a second dispute
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn redispute_allowed(mut self, redispute_allowed: bool) -> Self {
+        self.config.redispute_allowed = redispute_allowed;
+        return self
+    }
+
+    /// Sets how a negative total after a chargeback is handled.
+    ///
+    /// # Arguments
+    /// * negative_total_policy (NegativeTotalPolicy): the policy to apply
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn negative_total_policy(mut self, negative_total_policy: NegativeTotalPolicy) -> Self {
+        self.config.negative_total_policy = negative_total_policy;
+        return self
+    }
+
+    /// Sets the most disputes a client may have open at once, a dispute beyond it is rejected.
+    ///
+    /// # Arguments
+    /// * max_open_disputes (Option<usize>): the cap, None for unlimited
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn max_open_disputes(mut self, max_open_disputes: Option<usize>) -> Self {
+        self.config.max_open_disputes = max_open_disputes;
+        return self
+    }
+
+    /// Sets the number of decimal places amounts are parsed at for each currency, for example 0 for JPY or 8 for BTC.
+    ///
+    /// # Arguments
+    /// * currency_scales (HashMap<String, u32>): the scale for each currency code
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn currency_scales(mut self, currency_scales: HashMap<String, u32>) -> Self {
+        self.config.currency_scales = currency_scales;
+        return self
+    }
+
+    /// Sets where deposit and withdrawal transaction IDs have to be unique.
+    ///
+    /// # Arguments
+    /// * tx_id_scope (TxIdScope): the scope of the uniqueness check
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn tx_id_scope(mut self, tx_id_scope: TxIdScope) -> Self {
+        self.config.tx_id_scope = tx_id_scope;
+        return self
+    }
+
+    /// Sets how often the number of rows read is passed to the progress callback.
+    ///
+    /// # Arguments
+    /// * progress_interval (Option<usize>): the number of rows between updates, None for no progress
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn progress_interval(mut self, progress_interval: Option<usize>) -> Self {
+        self.config.progress_interval = progress_interval;
+        return self
+    }
+
+    /// Sets the callback that receives the number of rows read at every progress interval.
+    ///
+    /// # Arguments
+    /// * callback (F): called with the number of rows read so far
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn on_progress<F: Fn(usize) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.config.progress_callback = Some(ProgressCallback(Arc::new(callback)));
+        return self
+    }
+
+    /// Sets if rows with a different number of fields than the header are read instead of rejected.
+    ///
+    /// # Arguments
+    /// * flexible (bool): true to read them
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn flexible(mut self, flexible: bool) -> Self {
+        self.config.flexible = flexible;
+        return self
+    }
+
+    /// Sets what happens to a deposit or withdrawal that reuses a transaction ID.
+    ///
+    /// # Arguments
+    /// * duplicate_policy (DuplicatePolicy): the policy for duplicates
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn duplicate_policy(mut self, duplicate_policy: DuplicatePolicy) -> Self {
+        self.config.duplicate_policy = duplicate_policy;
+        return self
+    }
+
+    /// Sets where the funds of a resolved dispute go.
+    ///
+    /// # Arguments
+    /// * resolve_target (ResolveTarget): the target for resolved funds
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn resolve_target(mut self, resolve_target: ResolveTarget) -> Self {
+        self.config.resolve_target = resolve_target;
+        return self
+    }
+
+    /// Sets how funds move when a withdrawal is disputed.
+    ///
+    /// # Arguments
+    /// * withdrawal_dispute_mode (Option<WithdrawalDisputeMode>): the mode, None to ignore disputes on withdrawals
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn withdrawal_dispute_mode(mut self, withdrawal_dispute_mode: Option<WithdrawalDisputeMode>) -> Self {
+        self.config.withdrawal_dispute_mode = withdrawal_dispute_mode;
+        return self
+    }
+
+    /// Sets if lines starting with # are skipped as comments instead of being parsed as rows.
+    ///
+    /// # Arguments
+    /// * allow_comments (bool): true to skip comment lines
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn allow_comments(mut self, allow_comments: bool) -> Self {
+        self.config.allow_comments = allow_comments;
+        return self
+    }
+
+    /// Sets how close float balances must be to be treated as equal.
+    ///
+    /// # Arguments
+    /// * epsilon (f32): the tolerance
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.config.epsilon = epsilon;
+        return self
+    }
+
+    /// Sets the most accounts processing may create before it stops with an error.
+    ///
+    /// # Arguments
+    /// * max_accounts (Option<usize>): the limit, None for unlimited
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn max_accounts(mut self, max_accounts: Option<usize>) -> Self {
+        self.config.max_accounts = max_accounts;
+        return self
+    }
+
+    /// Sets the amount given to a deposit that has none.
+    ///
+    /// # Arguments
+    /// * default_deposit_amount (Option<f32>): the amount, None to reject deposits without an amount
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn default_deposit_amount(mut self, default_deposit_amount: Option<f32>) -> Self {
+        self.config.default_deposit_amount = default_deposit_amount;
+        return self
+    }
+
+    /// Sets what happens when a resolve or chargeback carries an amount that disagrees with the held amount.
+    ///
+    /// # Arguments
+    /// * amount_mismatch_policy (AmountMismatchPolicy): the policy to apply
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn amount_mismatch_policy(mut self, amount_mismatch_policy: AmountMismatchPolicy) -> Self {
+        self.config.amount_mismatch_policy = amount_mismatch_policy;
+        return self
+    }
+
+    /// Sets if the balances are rounded to the scale of the account currency after every transaction.
+    ///
+    /// # Arguments
+    /// * round_each_op (bool): true to round after every transaction
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn round_each_op(mut self, round_each_op: bool) -> Self {
+        self.config.round_each_op = round_each_op;
+        return self
+    }
+
+    /// Sets if deposits are still accepted by a locked account.
+    ///
+    /// # Arguments
+    /// * locked_accepts_deposits (bool): true to accept deposits into locked accounts
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn locked_accepts_deposits(mut self, locked_accepts_deposits: bool) -> Self {
+        self.config.locked_accepts_deposits = locked_accepts_deposits;
+        return self
+    }
+
+    /// Sets the character separating the input fields.
+    ///
+    /// # Arguments
+    /// * input_delimiter (u8): the delimiter
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn input_delimiter(mut self, input_delimiter: u8) -> Self {
+        self.config.input_delimiter = input_delimiter;
+        return self
+    }
+
+    /// Sets if the input starts with a header row.
+    ///
+    /// # Arguments
+    /// * has_headers (bool): false if the first row is data
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn has_headers(mut self, has_headers: bool) -> Self {
+        self.config.has_headers = has_headers;
+        return self
+    }
+
+    /// Sets if the delimiter and header row are guessed from the start of the input.
+    ///
+    /// # Arguments
+    /// * auto_detect (bool): true to guess the input format
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn auto_detect(mut self, auto_detect: bool) -> Self {
+        self.config.auto_detect = auto_detect;
+        return self
+    }
+
+    /// Sets if an input header with unknown columns stops processing.
+    ///
+    /// # Arguments
+    /// * strict_schema (bool): true to reject unknown columns
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn strict_schema(mut self, strict_schema: bool) -> Self {
+        self.config.strict_schema = strict_schema;
+        return self
+    }
+
+    /// Sets if a deposit with a negative amount is applied as a withdrawal of its magnitude.
+    ///
+    /// # Arguments
+    /// * negative_deposit_as_withdrawal (bool): true for feeds that sign withdrawals instead of typing them
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn negative_deposit_as_withdrawal(mut self, negative_deposit_as_withdrawal: bool) -> Self {
+        self.config.negative_deposit_as_withdrawal = negative_deposit_as_withdrawal;
+        return self
+    }
+
+    /// Sets the number of worker threads process_parallel partitions the clients across.
+    ///
+    /// # Arguments
+    /// * worker_threads (usize): the number of workers, 0 is treated as 1
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn worker_threads(mut self, worker_threads: usize) -> Self {
+        self.config.worker_threads = worker_threads;
+        return self
+    }
+
+    /// Sets the most transactions waiting for each worker before the reader blocks.
+    ///
+    /// # Arguments
+    /// * worker_queue_bound (usize): the bound of each worker queue
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn worker_queue_bound(mut self, worker_queue_bound: usize) -> Self {
+        self.config.worker_queue_bound = worker_queue_bound;
+        return self
+    }
+
+    /// Sets the human readable format amounts are cleaned of before they are parsed.
+    ///
+    /// # Arguments
+    /// * amount_format (Option<AmountFormat>): the format, None to parse amounts as they are
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn amount_format(mut self, amount_format: Option<AmountFormat>) -> Self {
+        self.config.amount_format = amount_format;
+        return self
+    }
+
+    /// Sets if the transaction logs are skipped for deposit and withdrawal only feeds, the used transaction IDs are not kept either
+    /// so the duplicate policy does not apply.
+    ///
+    /// # Arguments
+    /// * no_log (bool): true to keep only the balances
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn no_log(mut self, no_log: bool) -> Self {
+        self.config.no_log = no_log;
+        return self
+    }
+
+    /// Sets the clients whose transactions are processed or skipped on ingestion.
+    ///
+    /// # Arguments
+    /// * client_filter (Option<ClientFilter>): the include or exclude list, None for every client
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn client_filter(mut self, client_filter: Option<ClientFilter>) -> Self {
+        self.config.client_filter = client_filter;
+        return self
+    }
+
+    /// Sets if consecutive deposits are merged in the account logs, disputes, resolves and chargebacks are then rejected.
+    ///
+    /// # Arguments
+    /// * coalesce_deposits (bool): true to merge the deposits
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn coalesce_deposits(mut self, coalesce_deposits: bool) -> Self {
+        self.config.coalesce_deposits = coalesce_deposits;
+        return self
+    }
+
+    /// Sets if every file passed to process_files gets its own transaction ID space.
+    ///
+    /// # Arguments
+    /// * tx_namespace_per_file (bool): true to namespace the IDs by file
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn tx_namespace_per_file(mut self, tx_namespace_per_file: bool) -> Self {
+        self.config.tx_namespace_per_file = tx_namespace_per_file;
+        return self
+    }
+
+    /// Sets what happens to deposits and withdrawals beyond the range f32 holds exactly, 2^24 units at the scale of the account,
+    /// and to deposits that take a balance beyond it. The range is small: at the default scale of four places Reject refuses
+    /// amounts and balances from about 1677.7216 up, at two places from about 167772.16.
+    ///
+    /// # Arguments
+    /// * large_amount_policy (LargeAmountPolicy): the policy to apply
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn large_amount_policy(mut self, large_amount_policy: LargeAmountPolicy) -> Self {
+        self.config.large_amount_policy = large_amount_policy;
+        return self
+    }
+
+    /// Sets what happens when a dispute carries an amount that disagrees with the disputed transaction.
+    ///
+    /// # Arguments
+    /// * dispute_amount_policy (AmountMismatchPolicy): the policy to apply
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn dispute_amount_policy(mut self, dispute_amount_policy: AmountMismatchPolicy) -> Self {
+        self.config.dispute_amount_policy = dispute_amount_policy;
+        return self
+    }
+
+    /// Sets what happens to the other open disputes of an account when a chargeback locks it.
+    ///
+    /// # Arguments
+    /// * lock_hold_policy (LockHoldPolicy): leave, resolve or charge back the other holds
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn lock_hold_policy(mut self, lock_hold_policy: LockHoldPolicy) -> Self {
+        self.config.lock_hold_policy = lock_hold_policy;
+        return self
+    }
+
+    /// Sets the arithmetic the balances are kept in.
+    ///
+    /// # Arguments
+    /// * arithmetic (ArithmeticBackend): Float for speed or Decimal for sums that do not drift
+    ///
+    /// # Returns
+    /// * (Self): the builder with the option set
+    pub fn arithmetic(mut self, arithmetic: ArithmeticBackend) -> Self {
+        self.config.arithmetic = arithmetic;
+        return self
+    }
+
+    /// Finishes building the config.
+    ///
+    /// # Returns
+    /// * (EngineConfig): the built config
+    pub fn build(self) -> EngineConfig {
+        return self.config
+    }
+}
+
+
+/// This enum is responsible for defining which fields of the output are wrapped in quotes.
+///
+/// # Attributes
+/// * Always: every field is quoted
+/// * Necessary: only fields containing a delimiter, quote or line break are quoted
+/// * Never: no field is quoted
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum QuoteStyle {
+    Always,
+    #[default]
+    Necessary,
+    Never
+}
+
+impl FromStr for QuoteStyle {
+    type Err = String;
+
+    fn from_str(selection: &str) -> Result<Self, Self::Err> {
+        match selection {
+            "always" => {return Ok(QuoteStyle::Always)},
+            "necessary" => {return Ok(QuoteStyle::Necessary)},
+            "never" => {return Ok(QuoteStyle::Never)},
+            _ => {return Err(format!("quote style '{}' is not supported, expected always, necessary or never", selection))}
+        }
+    }
+}
+
+
+/// This enum is responsible for defining how a negative total is written for systems that can not take one.
+///
+/// # Attributes
+/// * Leave: the total is written as it is
+/// * Clamp: a negative total is written as zero
+/// * Flag: the total is written as it is and an overdrawn column is added, true for a negative total
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum NegativeTotalOutput {
+    #[default]
+    Leave,
+    Clamp,
+    Flag
+}
+
+impl FromStr for NegativeTotalOutput {
+    type Err = String;
+
+    fn from_str(selection: &str) -> Result<Self, Self::Err> {
+        match selection {
+            "leave" => {return Ok(NegativeTotalOutput::Leave)},
+            "clamp" => {return Ok(NegativeTotalOutput::Clamp)},
+            "flag" => {return Ok(NegativeTotalOutput::Flag)},
+            _ => {return Err(format!("negative total handling '{}' is not supported, expected leave, clamp or flag", selection))}
+        }
+    }
+}
+
+
+/// This enum is responsible for defining the columns that can be written for an account.
+///
+/// # Attributes
+/// * Client: the ID of the client
+/// * Available: the funds available
+/// * Held: the funds held by disputes
+/// * Total: the total funds
+/// * Locked: if the account is locked
+/// * TxCount: the number of accepted transactions
+/// * LockedByTx: the transaction that locked the account, blank if it is open
+/// * Overdrawn: if the total is negative
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Column {
+    Client,
+    Available,
+    Held,
+    Total,
+    Locked,
+    TxCount,
+    LockedByTx,
+    Overdrawn
+}
+
+impl Column {
+
+    /// Gets the name of the column as written in the header.
+    ///
+    /// # Returns
+    /// * (&'static str): the column name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Client => {return "client"},
+            Column::Available => {return "available"},
+            Column::Held => {return "held"},
+            Column::Total => {return "total"},
+            Column::Locked => {return "locked"},
+            Column::TxCount => {return "tx_count"},
+            Column::LockedByTx => {return "locked_by_tx"},
+            Column::Overdrawn => {return "overdrawn"}
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(selection: &str) -> Result<Self, Self::Err> {
+        match selection {
+            "client" => {return Ok(Column::Client)},
+            "available" => {return Ok(Column::Available)},
+            "held" => {return Ok(Column::Held)},
+            "total" => {return Ok(Column::Total)},
+            "locked" => {return Ok(Column::Locked)},
+            "tx_count" => {return Ok(Column::TxCount)},
+            "locked_by_tx" => {return Ok(Column::LockedByTx)},
+            "overdrawn" => {return Ok(Column::Overdrawn)},
+            _ => {return Err(format!("column '{}' is not known, expected client, available, held, total, locked, tx_count, locked_by_tx or overdrawn", selection))}
+        }
+    }
+}
+
+
+/// This struct is responsible for housing the options that change how accounts are written.
+///
+/// # Attributes
+/// * tx_count (bool): if true a tx_count column with the number of accepted transactions is added
+/// * locked_only (bool): if true only the locked accounts are written
+/// * locked_by_tx (bool): if true a locked_by_tx column with the transaction that locked each account is added
+/// * delimiter (u8): the byte separating the CSV fields
+/// * decimal_separator (char): the character separating the whole and fractional parts of amounts
+/// * quote_style (QuoteStyle): which fields are wrapped in quotes
+/// * column_order (Option<Vec<Column>>): the columns to write in order, None for client, available, held, total, locked
+/// * fixed_scale (Option<u32>): if set amounts are written as plain decimals with exactly this many decimal places, never in scientific notation
+/// * negative_total (NegativeTotalOutput): if a negative total is written as it is, as zero or with an overdrawn column
+#[derive(Debug, PartialEq, Clone)]
+pub struct OutputOptions {
+    pub tx_count: bool,
+    pub locked_only: bool,
+    pub locked_by_tx: bool,
+    pub delimiter: u8,
+    pub decimal_separator: char,
+    pub quote_style: QuoteStyle,
+    pub column_order: Option<Vec<Column>>,
+    pub fixed_scale: Option<u32>,
+    pub negative_total: NegativeTotalOutput
+}
+
+impl Default for OutputOptions {
+
+    fn default() -> Self {
+        return OutputOptions{tx_count: false, locked_only: false, locked_by_tx: false, delimiter: b',', decimal_separator: '.', quote_style: QuoteStyle::Necessary, column_order: None, fixed_scale: None, negative_total: NegativeTotalOutput::Leave}
+    }
+}
+
+impl OutputOptions {
+
+    /// Checks that the options can produce CSV that reads back unambiguously.
+    ///
+    /// # Returns
+    /// * (Result<(), String>): an error if the delimiter and decimal separator are the same character or a column is repeated
+    pub fn validate(&self) -> Result<(), String> {
+        if self.decimal_separator == self.delimiter as char {
+            return Err(format!("the decimal separator and the delimiter can not both be '{}'", self.decimal_separator))
+        }
+        let columns = self.columns();
+        for (index, column) in columns.iter().enumerate() {
+            if columns[..index].contains(column) {
+                return Err(format!("the column '{}' is listed more than once", column.name()))
+            }
+        }
+        return Ok(())
+    }
+
+    /// Gets the columns that are written in order, adding tx_count, locked_by_tx and then overdrawn at the end if they are enabled and not already placed.
+    ///
+    /// # Returns
+    /// * (Vec<Column>): the columns of the header and every row
+    pub fn columns(&self) -> Vec<Column> {
+        let mut columns = match &self.column_order {
+            Some(column_order) => column_order.clone(),
+            None => vec![Column::Client, Column::Available, Column::Held, Column::Total, Column::Locked]
+        };
+        if self.tx_count && !columns.contains(&Column::TxCount) {
+            columns.push(Column::TxCount);
+        }
+        if self.locked_by_tx && !columns.contains(&Column::LockedByTx) {
+            columns.push(Column::LockedByTx);
+        }
+        if self.negative_total == NegativeTotalOutput::Flag && !columns.contains(&Column::Overdrawn) {
+            columns.push(Column::Overdrawn);
+        }
+        return columns
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+use super::super::accounts::account_map::InvariantError;
+use super::super::transactions::ids::ClientId;
+use super::verify::VerifyResult;
+
+
+/// This enum is responsible for defining the errors that stop the engine from processing a stream.
+///
+/// # Attributes
+/// * Io: the input or output could not be read or written
+/// * Csv: the CSV reader or writer failed outside of a specific row
+/// * Parse: a row could not be turned into a transaction
+/// * NegativeBalances: the listed clients ended with negative available funds when that is not allowed
+/// * InvalidOptions: the options given can not be used together
+/// * TooManyAccounts: a transaction would create an account beyond the configured maximum
+/// * UnknownColumns: the input header has columns outside the known set when the schema is strict
+/// * InvariantViolations: accounts broke the balance rules in the final check
+/// * Unverified: a claimed output did not match the one the engine writes
+#[derive(Debug)]
+pub enum EngineError {
+    Io(std::io::Error),
+    Csv(csv::Error),
+    Parse { line: u64, message: String },
+    NegativeBalances(Vec<ClientId>),
+    InvalidOptions(String),
+    TooManyAccounts(usize),
+    UnknownColumns(Vec<String>),
+    InvariantViolations(Vec<InvariantError>),
+    Unverified(VerifyResult)
+}
+
+impl fmt::Display for EngineError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Io(error) => {return write!(f, "io error: {}", error)},
+            EngineError::Csv(error) => {return write!(f, "csv error: {}", error)},
+            EngineError::Parse { line, message } => {return write!(f, "line {}: {}", line, message)},
+            EngineError::NegativeBalances(clients) => {
+                let listed = clients.iter().map(|client| client.to_string()).collect::<Vec<String>>().join(", ");
+                return write!(f, "clients with negative available funds: {}", listed)
+            },
+            EngineError::InvalidOptions(message) => {return write!(f, "invalid options: {}", message)},
+            EngineError::TooManyAccounts(max_accounts) => {return write!(f, "more than {} accounts", max_accounts)},
+            EngineError::UnknownColumns(columns) => {return write!(f, "unknown columns: {}", columns.join(", "))},
+            EngineError::InvariantViolations(violations) => {
+                let listed = violations.iter().map(|violation| violation.to_string()).collect::<Vec<String>>().join("; ");
+                return write!(f, "invariants broken: {}", listed)
+            },
+            EngineError::Unverified(result) => {return write!(f, "{}", result)}
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<std::io::Error> for EngineError {
+
+    fn from(error: std::io::Error) -> Self {
+        return EngineError::Io(error)
+    }
+}
+
+impl From<csv::Error> for EngineError {
+
+    fn from(error: csv::Error) -> Self {
+        return EngineError::Csv(error)
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::errors::EngineError;
+
+
+/// Installs a SIGINT handler that asks processing to stop instead of killing the process.
+///
+/// # Returns
+/// * (Result<Arc<AtomicBool>, EngineError>): the flag set when the signal arrives, to be passed to process_transactions_until
+pub fn install_handler() -> Result<Arc<AtomicBool>, EngineError> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_flag = interrupted.clone();
+
+    ctrlc::set_handler(move || handler_flag.store(true, Ordering::SeqCst)).map_err(Error::other)?;
+    return Ok(interrupted)
+}
+
+
+#[cfg(test)]
+mod interrupt_tests {
+
+    use std::io::Read;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::super::{process_transactions_until, write_accounts};
+    use super::super::config::{EngineConfig, OutputOptions};
+
+    /// Hands out one line per read and sets the flag once a number of lines have been read, standing in for a SIGINT mid-file.
+    struct InterruptingReader<'a> {
+        lines: Vec<&'static str>,
+        interrupt_after: usize,
+        interrupted: &'a AtomicBool
+    }
+
+    impl Read for InterruptingReader<'_> {
+
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            if self.lines.is_empty() {
+                return Ok(0)
+            }
+            let line = self.lines.remove(0).as_bytes();
+            buffer[..line.len()].copy_from_slice(line);
+            self.interrupt_after -= 1;
+            if self.interrupt_after == 0 {
+                self.interrupted.store(true, Ordering::SeqCst);
+            }
+            return Ok(line.len())
+        }
+    }
+
+    #[test]
+    fn test_interrupt_keeps_processed_rows() {
+        let interrupted = AtomicBool::new(false);
+        let reader = InterruptingReader{
+            lines: vec!["type,client,tx,amount\n", "deposit,1,1,1.0\n", "deposit,2,2,2.0\n", "deposit,3,3,3.0\n"],
+            interrupt_after: 3,
+            interrupted: &interrupted
+        };
+
+        let account_map = process_transactions_until(reader, &EngineConfig::default(), &interrupted).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_accounts(account_map, &mut output, &OutputOptions::default()).unwrap();
+
+        assert_eq!("client,available,held,total,locked\n1,1.0,0.0,1.0,false\n2,2.0,0.0,2.0,false\n", String::from_utf8(output).unwrap());
+    }
+}
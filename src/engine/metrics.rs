@@ -0,0 +1,67 @@
+use std::fmt::Write;
+
+use super::super::accounts::account_map::{AccountMap, ErrorLogOrder};
+use super::super::transactions::enums::TransactionType;
+use super::super::transactions::enums::TransactionType::{CHARGEBACK, DEPOSIT, DISPUTE, FREEZE, RESOLVE, WITHDRAWAL};
+use super::super::transactions::transaction::Transaction;
+
+
+/// The transaction types in the order their counters are rendered.
+const TRANSACTION_TYPES: [TransactionType; 6] = [DEPOSIT, WITHDRAWAL, DISPUTE, RESOLVE, CHARGEBACK, FREEZE];
+
+
+/// Renders counters for a processed map of accounts in the Prometheus text format so they can be scraped.
+///
+/// # Arguments
+/// * map (&AccountMap): the processed accounts
+///
+/// # Returns
+/// * (String): the metrics, one line per sample
+pub fn render_metrics(map: &AccountMap) -> String {
+    let mut rendered = String::new();
+    let count = |log: &[&Transaction], transaction_type: &TransactionType| {
+        return log.iter().filter(|transaction| &transaction.transaction_type == transaction_type).count()
+    };
+    let accepted: Vec<&Transaction> = map.total_transaction_log.iter().collect();
+    let rejected = map.error_log(ErrorLogOrder::Input);
+
+    // writing to a String can not fail
+    writeln!(rendered, "# HELP transactions_total Transactions processed by type and outcome.").unwrap();
+    writeln!(rendered, "# TYPE transactions_total counter").unwrap();
+    for transaction_type in TRANSACTION_TYPES.iter() {
+        let label = format!("{:?}", transaction_type).to_lowercase();
+        writeln!(rendered, "transactions_total{{type=\"{}\",outcome=\"accepted\"}} {}", label, count(&accepted, transaction_type)).unwrap();
+        writeln!(rendered, "transactions_total{{type=\"{}\",outcome=\"rejected\"}} {}", label, count(&rejected, transaction_type)).unwrap();
+    }
+    writeln!(rendered, "# HELP accounts_total Accounts in the map.").unwrap();
+    writeln!(rendered, "# TYPE accounts_total gauge").unwrap();
+    writeln!(rendered, "accounts_total {}", map.accounts.len()).unwrap();
+    writeln!(rendered, "# HELP locked_accounts_total Accounts that have been locked.").unwrap();
+    writeln!(rendered, "# TYPE locked_accounts_total gauge").unwrap();
+    writeln!(rendered, "locked_accounts_total {}", map.accounts.values().filter(|account| account.is_locked()).count()).unwrap();
+    return rendered
+}
+
+
+#[cfg(test)]
+mod metrics_tests {
+
+    use super::render_metrics;
+    use super::super::process_bytes;
+
+    #[test]
+    fn test_render_metrics() {
+        let input = "type,client,tx,amount\ndeposit,1,1,2.0\ndeposit,2,2,1.0\nwithdrawal,1,3,5.0\ndispute,2,2,\nchargeback,2,2,\n";
+        let account_map = process_bytes(input.as_bytes()).unwrap();
+        let rendered = render_metrics(&account_map);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert!(lines.contains(&"# TYPE transactions_total counter"));
+        assert!(lines.contains(&"transactions_total{type=\"deposit\",outcome=\"accepted\"} 2"));
+        assert!(lines.contains(&"transactions_total{type=\"withdrawal\",outcome=\"rejected\"} 1"));
+        assert!(lines.contains(&"transactions_total{type=\"chargeback\",outcome=\"accepted\"} 1"));
+        assert!(lines.contains(&"transactions_total{type=\"resolve\",outcome=\"accepted\"} 0"));
+        assert!(lines.contains(&"accounts_total 2"));
+        assert!(lines.contains(&"locked_accounts_total 1"));
+    }
+}
@@ -0,0 +1,1026 @@
+pub mod config;
+pub mod errors;
+pub mod metrics;
+pub mod namespace;
+pub mod parallel;
+pub mod sniff;
+pub mod stats;
+pub mod verify;
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "interrupt")]
+pub mod interrupt;
+#[cfg(feature = "parquet")]
+pub mod columnar;
+#[cfg(feature = "zip")]
+pub mod archive;
+
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use csv::StringRecord;
+
+use config::{Column, EngineConfig, OnError, OutputOptions, ProgressCallback, QuoteStyle};
+use errors::EngineError;
+use namespace::TxNamespace;
+use sniff::{sniff_format, InputFormat, DEFAULT_HEADERS};
+use stats::EngineStats;
+use super::accounts::{log_transaction, TransactionOutcome};
+use super::accounts::account_map::AccountMap;
+use super::data_access_layer::schema::{TransactionSchema, AccountSchema, KNOWN_COLUMNS};
+use super::transactions::ids::ClientId;
+use super::transactions::transaction::Transaction;
+
+
+/// Describes why a record could not be deserialised, naming the column and the raw value when the error points at a field.
+///
+/// # Arguments
+/// * error (&csv::Error): the error returned by deserialising the record
+/// * record (&StringRecord): the record that failed
+/// * headers (&StringRecord): the header row used to name the fields
+///
+/// # Returns
+/// * (String): the message for the bad row
+fn describe_deserialize_error(error: &csv::Error, record: &StringRecord, headers: &StringRecord) -> String {
+    if let csv::ErrorKind::Deserialize{err, ..} = error.kind() {
+        let field = err.field().map(|index| index as usize);
+        let column = field.and_then(|index| headers.get(index));
+        let value = field.and_then(|index| record.get(index));
+
+        if let (Some(column), Some(value)) = (column, value) {
+            match err.kind() {
+                csv::DeserializeErrorKind::ParseInt(_) => {return format!("column '{}' value '{}' is not an integer", column, value)},
+                _ => {return format!("column '{}' value '{}' is not valid: {}", column, value, err.kind())}
+            }
+        }
+    }
+    return error.to_string()
+}
+
+
+/// Turns a single CSV record into a transaction.
+///
+/// # Arguments
+/// * result (Result<StringRecord, csv::Error>): the record as read by the CSV reader
+/// * headers (&StringRecord): the header row used to name the fields
+/// * config (&EngineConfig): the config holding the scale amounts are parsed at
+///
+/// # Returns
+/// * (Result<(Option<i64>, Transaction), EngineError>): the timestamp of the row and its transaction, the line and reason it could not be parsed,
+///   or the CSV error itself if it is not tied to a line, such as the input failing to read
+fn parse_record(result: Result<StringRecord, csv::Error>, headers: &StringRecord, config: &EngineConfig) -> Result<(Option<i64>, Transaction), EngineError> {
+    match result {
+        Ok(mut record) => {
+            let line = record.position().map(|position| position.line()).unwrap_or(0);
+            // only a flexible reader lets a long row through, the fields past the header are ignored
+            record.truncate(headers.len());
+            let mut raw_transaction = record.deserialize::<TransactionSchema>(Some(headers))
+                                        .map_err(|error| EngineError::Parse{line, message: describe_deserialize_error(&error, &record, headers)})?;
+            if let Some(amount_format) = &config.amount_format {
+                raw_transaction.amount = raw_transaction.amount.map(|raw| amount_format.sanitize(&raw));
+            }
+            let timestamp = raw_transaction.timestamp;
+            let scale = config.scale_for(raw_transaction.currency.as_deref());
+            let transaction = raw_transaction.convert_to_transaction(scale)
+                                             .map_err(|message| EngineError::Parse{line, message})?;
+            return Ok((timestamp, transaction))
+        },
+        Err(error) => {
+            let line = match error.position() {
+                Some(position) => position.line(),
+                None => {return Err(EngineError::from(error))}
+            };
+            if let csv::ErrorKind::UnequalLengths{expected_len, len, ..} = error.kind() {
+                return Err(EngineError::Parse{line, message: format!("expected {} fields but found {}", expected_len, len)})
+            }
+            return Err(EngineError::Parse{line, message: error.to_string()})
+        }
+    }
+}
+
+
+/// Applies a transaction unless it would create an account beyond the configured maximum, acknowledging it if asked.
+///
+/// # Arguments
+/// * account_map (AccountMap): the accounts so far
+/// * transaction (Transaction): the transaction to apply
+/// * config (&EngineConfig): the config holding the maximum number of accounts
+/// * acks (Option<&mut dyn Write>): where a `tx,outcome` line is written once the transaction has been handled
+///
+/// # Returns
+/// * (Result<AccountMap, EngineError>): the accounts after the transaction, or TooManyAccounts
+fn apply_transaction(account_map: AccountMap, transaction: Transaction, config: &EngineConfig, acks: Option<&mut (dyn Write + '_)>) -> Result<AccountMap, EngineError> {
+    if let Some(max_accounts) = config.max_accounts {
+        if !account_map.accounts.contains_key(&transaction.client) && account_map.accounts.len() >= max_accounts {
+            return Err(EngineError::TooManyAccounts(max_accounts))
+        }
+    }
+    let tx = transaction.tx;
+    let (account_map, outcome) = log_transaction(Some(account_map), transaction);
+
+    if let Some(acks) = acks {
+        let outcome = if outcome == TransactionOutcome::Applied {"accepted"} else {"rejected"};
+        writeln!(acks, "{},{}", tx, outcome)?;
+    }
+    return Ok(account_map)
+}
+
+
+/// Applies every transaction from an iterator to a fresh map of accounts without going through CSV.
+///
+/// # Arguments
+/// * iter (I): the transactions, applied one at a time in the order they are yielded
+///
+/// # Returns
+/// * (AccountMap): the accounts after every transaction has been applied
+pub fn process_iter<I: IntoIterator<Item = Transaction>>(iter: I) -> AccountMap {
+    let mut account_map = AccountMap::new();
+
+    for transaction in iter {
+        (account_map, _) = log_transaction(Some(account_map), transaction);
+    }
+    return account_map
+}
+
+
+/// Reads every transaction from a CSV source and applies it to a fresh map of accounts.
+///
+/// # Arguments
+/// * input (R): the CSV data with a header row
+/// * config (&EngineConfig): the parsing options and the policies applied to the transactions
+///
+/// # Returns
+/// * (Result<AccountMap, EngineError>): the accounts after processing, or the first bad row when failing fast
+pub fn process_transactions<R: Read>(input: R, config: &EngineConfig) -> Result<AccountMap, EngineError> {
+    return process_transactions_until(input, config, &AtomicBool::new(false))
+}
+
+
+/// Reads every transaction from CSV data already in memory with the default config.
+///
+/// # Arguments
+/// * data (&[u8]): the CSV data with a header row
+///
+/// # Returns
+/// * (Result<AccountMap, EngineError>): the accounts after processing
+pub fn process_bytes(data: &[u8]) -> Result<AccountMap, EngineError> {
+    return process_transactions(Cursor::new(data), &EngineConfig::default())
+}
+
+
+/// Reads transactions from a CSV source until it ends or the stop flag is set, applying them to a fresh map of accounts.
+///
+/// # Arguments
+/// * input (R): the CSV data with a header row
+/// * config (&EngineConfig): the parsing options and the policies applied to the transactions
+/// * stop (&AtomicBool): checked before every row, once it is true no more rows are read
+///
+/// # Returns
+/// * (Result<AccountMap, EngineError>): the accounts after the rows read so far, or the first bad row when failing fast
+pub fn process_transactions_until<R: Read>(input: R, config: &EngineConfig, stop: &AtomicBool) -> Result<AccountMap, EngineError> {
+    return process_records(AccountMap::new().with_config(config.clone()), input, config, stop, None, None)
+}
+
+
+/// Reads every transaction from a CSV source, writing a `tx,outcome` line to the acks for each one as it is handled.
+/// A row skipped by the client filter is acknowledged as `tx,filtered` and a row that could not be parsed as `line,parse_error`.
+///
+/// # Arguments
+/// * input (R): the CSV data with a header row
+/// * config (&EngineConfig): the parsing options and the policies applied to the transactions
+/// * acks (W): where the acknowledgements are written, separate from the account output
+///
+/// # Returns
+/// * (Result<AccountMap, EngineError>): the accounts after processing, or an error if an acknowledgement could not be written
+pub fn process_transactions_with_acks<R: Read, W: Write>(input: R, config: &EngineConfig, mut acks: W) -> Result<AccountMap, EngineError> {
+    let account_map = process_records(AccountMap::new().with_config(config.clone()), input, config, &AtomicBool::new(false), Some(&mut acks), None)?;
+    acks.flush()?;
+    return Ok(account_map)
+}
+
+
+/// Reads every transaction from several CSV sources in turn, applying them all to the same map of accounts.
+/// With tx_namespace_per_file each file gets its own transaction IDs, a dispute only finds a deposit from the same file
+/// and the accounts are keyed on internal IDs whose file and original ID can be found in the returned namespace.
+///
+/// # Arguments
+/// * inputs (Vec<R>): the CSV sources in the order they are applied
+/// * config (&EngineConfig): the parsing options and the policies applied to the transactions
+///
+/// # Returns
+/// * (Result<(AccountMap, TxNamespace), EngineError>): the accounts after every file and the IDs handed out, empty without namespacing
+pub fn process_files<R: Read>(inputs: Vec<R>, config: &EngineConfig) -> Result<(AccountMap, TxNamespace), EngineError> {
+    let mut account_map = AccountMap::new().with_config(config.clone());
+    let mut namespace = TxNamespace::new();
+    let stop = AtomicBool::new(false);
+
+    for (file, input) in inputs.into_iter().enumerate() {
+        let file_namespace = if config.tx_namespace_per_file {Some((file, &mut namespace))} else {None};
+        account_map = process_records(account_map, input, config, &stop, None, file_namespace)?;
+    }
+    return Ok((account_map, namespace))
+}
+
+
+/// This enum is responsible for describing what a single row of the input turned into.
+///
+/// # Attributes
+/// * Parsed: the transaction the row holds
+/// * ParseError: the line of the row and the reason it could not be parsed
+#[derive(Debug, Clone)]
+pub enum RowResult {
+    Parsed(Transaction),
+    ParseError { line: u64, message: String }
+}
+
+
+/// Sets up a CSV reader over the input and turns it into a stream of rows, parsed one at a time as they are read.
+///
+/// # Arguments
+/// * input (R): the CSV data
+/// * config (&EngineConfig): the parsing options
+///
+/// # Returns
+/// * (Result<impl Iterator<Item = Result<(Option<i64>, RowResult), EngineError>>, EngineError>): the timestamp and outcome of every row or an error
+///   that stops the reading, or an error if the header could not be read
+fn rows<'a, R: Read + 'a>(input: R, config: &'a EngineConfig) -> Result<impl Iterator<Item = Result<(Option<i64>, RowResult), EngineError>> + 'a, EngineError> {
+    let mut input = BufReader::new(input);
+    let format = if config.auto_detect {
+        // the sample is only peeked at so the reader still starts from the first byte
+        sniff_format(input.fill_buf()?)
+    }
+    else {
+        InputFormat{delimiter: config.input_delimiter, has_headers: config.has_headers}
+    };
+    let comment = if config.allow_comments {Some(b'#')} else {None};
+    let mut reader = csv::ReaderBuilder::new().delimiter(format.delimiter).has_headers(format.has_headers)
+                                               .flexible(config.flexible).comment(comment).from_reader(input);
+    let headers = if format.has_headers {reader.headers()?.clone()} else {StringRecord::from(DEFAULT_HEADERS.to_vec())};
+    if config.strict_schema {
+        let unknown: Vec<String> = headers.iter().filter(|column| !KNOWN_COLUMNS.contains(column)).map(|column| column.to_string()).collect();
+        if !unknown.is_empty() {
+            return Err(EngineError::UnknownColumns(unknown))
+        }
+    }
+    return Ok(reader.into_records().map(move |result| {
+        match parse_record(result, &headers, config) {
+            Ok((timestamp, transaction)) => {return Ok((timestamp, RowResult::Parsed(transaction)))},
+            Err(EngineError::Parse{line, message}) => {return Ok((None, RowResult::ParseError{line, message}))},
+            Err(error) => {return Err(error)}
+        }
+    }))
+}
+
+
+/// Parses every row of a CSV source without applying any of them, so the caller decides what to do with each row.
+///
+/// # Arguments
+/// * input (R): the CSV data
+/// * config (&EngineConfig): the parsing options
+///
+/// # Returns
+/// * (Result<Vec<RowResult>, EngineError>): the outcome of every row in file order, or an error if the header or the input could not be read
+pub fn parse_rows<R: Read>(input: R, config: &EngineConfig) -> Result<Vec<RowResult>, EngineError> {
+    return rows(input, config)?.map(|row| row.map(|(_, row)| row)).collect()
+}
+
+
+/// The reading loop shared by the process functions.
+///
+/// # Arguments
+/// * account_map (AccountMap): the accounts the transactions are applied to
+/// * input (R): the CSV data with a header row
+/// * config (&EngineConfig): the parsing options and the policies applied to the transactions
+/// * stop (&AtomicBool): checked before every row, once it is true no more rows are read
+/// * acks (Option<&mut dyn Write>): where each handled transaction is acknowledged, None to skip acknowledgements
+/// * namespace (Option<(usize, &mut TxNamespace)>): the index of the file and the namespace its transaction IDs are mapped through
+///
+/// # Returns
+/// * (Result<AccountMap, EngineError>): the accounts after the rows read so far, or the first fatal error
+fn process_records<R: Read>(mut account_map: AccountMap, input: R, config: &EngineConfig, stop: &AtomicBool, mut acks: Option<&mut dyn Write>, mut namespace: Option<(usize, &mut TxNamespace)>) -> Result<AccountMap, EngineError> {
+    let mut rows = rows(input, config)?;
+    let mut batch: Vec<(Option<i64>, Transaction)> = Vec::new();
+
+    let mut rows_read: usize = 0;
+
+    // the flag is checked before reading so a row that has been read is always applied
+    while !stop.load(Ordering::SeqCst) {
+        let (timestamp, row) = match rows.next() {
+            Some(row) => row?,
+            None => {break}
+        };
+        rows_read += 1;
+        if let (Some(interval), Some(ProgressCallback(callback))) = (config.progress_interval, &config.progress_callback) {
+            if rows_read.is_multiple_of(interval) {
+                callback(rows_read);
+            }
+        }
+        match row {
+            RowResult::Parsed(mut transaction) => {
+                if let Some((file, namespace)) = namespace.as_mut() {
+                    transaction.tx = namespace.resolve(*file, transaction.tx);
+                }
+                if config.client_filter.as_ref().is_some_and(|filter| !filter.allows(transaction.client)) {
+                    if let Some(acks) = acks.as_deref_mut() {
+                        writeln!(acks, "{},filtered", transaction.tx)?;
+                    }
+                    continue
+                }
+                if config.sort_by_timestamp {
+                    batch.push((timestamp, transaction));
+                }
+                else {
+                    account_map = apply_transaction(account_map, transaction, config, acks.as_deref_mut())?;
+                }
+            },
+            RowResult::ParseError{line, message} => {
+                match config.on_error {
+                    OnError::Continue => {
+                        if let Some(acks) = acks.as_deref_mut() {
+                            writeln!(acks, "{},parse_error", line)?;
+                        }
+                        account_map.skipped_rows.push((line, message));
+                    },
+                    OnError::Fail => {return Err(EngineError::Parse{line, message})}
+                }
+            }
+        }
+    }
+
+    // the sort is stable so rows with the same timestamp keep their file order
+    batch.sort_by_key(|entry| entry.0);
+    for (_, transaction) in batch {
+        account_map = apply_transaction(account_map, transaction, config, acks.as_deref_mut())?;
+    }
+    return Ok(account_map)
+}
+
+
+/// Writes the state of every account as CSV.
+///
+/// # Arguments
+/// * account_map (AccountMap): the processed accounts to be written
+/// * output (W): where the CSV is written to
+/// * options (&OutputOptions): the optional columns, number format and which accounts to write
+///
+/// # Returns
+/// * (Result<(), EngineError>): an error if a row could not be written
+pub fn write_accounts<W: Write>(account_map: AccountMap, output: W, options: &OutputOptions) -> Result<(), EngineError> {
+    options.validate().map_err(EngineError::InvalidOptions)?;
+
+    let write_tx_count = options.columns().contains(&Column::TxCount);
+    let buffer = account_map.to_schemas().into_iter().filter(|schema| !options.locked_only || schema.locked).map(|mut schema| {
+        if write_tx_count {
+            schema.tx_count = Some(account_map.accounts[&ClientId(schema.client)].transaction_log.len());
+        }
+        schema
+    }).collect::<Vec<AccountSchema>>();
+    let quote_style = match options.quote_style {
+        QuoteStyle::Always => csv::QuoteStyle::Always,
+        QuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+        QuoteStyle::Never => csv::QuoteStyle::Never
+    };
+    let mut wtr = csv::WriterBuilder::new().delimiter(options.delimiter).quote_style(quote_style).from_writer(output);
+
+    let written = wtr.write_record(AccountSchema::header(options)).and_then(|_| {
+        return buffer.into_iter().try_for_each(|account| wtr.write_record(account.to_record(options)))
+    });
+    // flush on the error path as well so the rows written before a failure are not lost in the buffer
+    let flushed = wtr.flush();
+    written?;
+    flushed?;
+    return Ok(())
+}
+
+
+/// Checks that no account has ended with negative available funds.
+///
+/// # Arguments
+/// * account_map (&AccountMap): the processed accounts
+///
+/// # Returns
+/// * (Result<(), EngineError>): an error listing the clients with negative available funds
+pub fn check_no_negative(account_map: &AccountMap) -> Result<(), EngineError> {
+    let clients = account_map.negative_available();
+    if !clients.is_empty() {
+        return Err(EngineError::NegativeBalances(clients))
+    }
+    return Ok(())
+}
+
+
+/// Writes a dry-run report of what processing did without the account balances.
+///
+/// # Arguments
+/// * account_map (&AccountMap): the processed accounts
+/// * output (W): where the report is written to
+///
+/// # Returns
+/// * (Result<(), EngineError>): an error if the report could not be written
+pub fn write_validation_report<W: Write>(account_map: &AccountMap, mut output: W) -> Result<(), EngineError> {
+    let stats = EngineStats::from_map(account_map);
+    writeln!(output, "accepted: {}", stats.accepted_transactions)?;
+    writeln!(output, "rejected: {}", stats.rejected_transactions)?;
+
+    for (transaction, _) in &account_map.total_error_transaction_log {
+        writeln!(output, "rejected {:?} tx {} for client {}", transaction.transaction_type, transaction.tx, transaction.client)?;
+    }
+    output.flush()?;
+    return Ok(())
+}
+
+
+#[cfg(test)]
+mod engine_tests {
+
+    use std::collections::HashSet;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use super::{process_bytes, process_files, process_iter, process_transactions, process_transactions_with_acks, parse_rows, write_accounts, RowResult};
+    use super::AccountMap;
+    use super::errors::EngineError;
+    use super::config::{ClientFilter, Column, EngineConfig, NegativeTotalOutput, OnError, OutputOptions, QuoteStyle};
+    use super::Transaction;
+    use super::super::transactions::enums::TransactionType::{DEPOSIT, DISPUTE, WITHDRAWAL};
+    use super::super::transactions::amount::AmountFormat;
+    use super::super::transactions::errors::TransactionError;
+    use super::super::transactions::ids::{ClientId, TxId};
+
+    /// Builds a config that stops at the first bad row so tests fail loudly.
+    fn fail_fast() -> EngineConfig {
+        return EngineConfig::builder().on_error(OnError::Fail).build()
+    }
+
+    #[test]
+    fn test_output_is_reproducible() {
+        let mut input = String::from("type,client,tx,amount\n");
+        for client in 1..50 {
+            input.push_str(&format!("deposit,{},{},1.5\n", client, client));
+        }
+        let mut outputs: Vec<Vec<u8>> = Vec::new();
+
+        for _ in 0..2 {
+            let account_map = process_transactions(input.as_bytes(), &fail_fast()).unwrap();
+            let mut output: Vec<u8> = Vec::new();
+            write_accounts(account_map, &mut output, &OutputOptions::default()).unwrap();
+            outputs.push(output);
+        }
+        assert_eq!(50, String::from_utf8(outputs[0].clone()).unwrap().lines().count());
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[test]
+    fn test_tx_count_column() {
+        let input = "type,client,tx,amount\ndeposit,1,1,2.0\ndeposit,1,2,2.0\nwithdrawal,1,3,10.0\n";
+        let options = OutputOptions{tx_count: true, ..OutputOptions::default()};
+
+        let account_map = process_bytes(input.as_bytes()).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_accounts(account_map, &mut output, &options).unwrap();
+
+        assert_eq!("client,available,held,total,locked,tx_count\n1,4.0,0.0,4.0,false,2\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_default_columns() {
+        let input = "type,client,tx,amount\ndeposit,1,1,2.0\n";
+
+        let account_map = process_bytes(input.as_bytes()).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_accounts(account_map, &mut output, &OutputOptions::default()).unwrap();
+
+        assert_eq!("client,available,held,total,locked\n1,2.0,0.0,2.0,false\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_missing_amount_column() {
+        let input = "type,client,tx\ndeposit,1,1\ndispute,1,1\n";
+
+        let account_map = process_transactions(input.as_bytes(), &fail_fast()).unwrap();
+
+        assert_eq!(1, account_map.total_error_transaction_log.len());
+        assert_eq!(TxId(1), account_map.total_error_transaction_log[0].0.tx);
+        assert_eq!(1, account_map.total_transaction_log.len());
+        assert_eq!(None, account_map.total_transaction_log[0].amount);
+    }
+
+    #[test]
+    fn test_sort_by_timestamp() {
+        let input = "type,client,tx,amount,timestamp\ndispute,1,1,,20\ndeposit,1,1,3.0,10\ndeposit,1,2,1.0,30\n";
+
+        let unsorted = process_transactions(input.as_bytes(), &fail_fast()).unwrap();
+        assert_eq!(4.0, unsorted.accounts[&ClientId(1)].available());
+        assert_eq!(0.0, unsorted.accounts[&ClientId(1)].held());
+
+        let config = EngineConfig::builder().on_error(OnError::Fail).sort_by_timestamp(true).build();
+        let sorted = process_transactions(input.as_bytes(), &config).unwrap();
+        assert_eq!(1.0, sorted.accounts[&ClientId(1)].available());
+        assert_eq!(3.0, sorted.accounts[&ClientId(1)].held());
+        assert_eq!(4.0, sorted.accounts[&ClientId(1)].total());
+    }
+
+    #[test]
+    fn test_sort_applies_dispute_before_lock() {
+        // the dispute on tx 2 is dated before the chargeback that locks the account but comes after it in the file
+        let input = "type,client,tx,amount,timestamp\ndeposit,1,1,3.0,10\ndeposit,1,2,2.0,15\ndispute,1,1,,20\nchargeback,1,1,,30\ndispute,1,2,,25\n";
+
+        let unsorted = process_transactions(input.as_bytes(), &EngineConfig::default()).unwrap();
+        assert_eq!(0.0, unsorted.accounts[&ClientId(1)].held());
+        assert_eq!(1, unsorted.total_error_transaction_log.len());
+
+        let config = EngineConfig::builder().sort_by_timestamp(true).build();
+        let sorted = process_transactions(input.as_bytes(), &config).unwrap();
+        let account = &sorted.accounts[&ClientId(1)];
+        assert!(account.locked);
+        assert_eq!(Some(TxId(1)), account.locked_by_tx());
+        assert_eq!(0.0, account.available());
+        assert_eq!(2.0, account.held());
+        assert_eq!(2.0, account.total());
+        assert!(sorted.total_error_transaction_log.is_empty());
+    }
+
+    #[test]
+    fn test_sort_without_timestamps_keeps_file_order() {
+        let input = "type,client,tx,amount\ndeposit,1,1,3.0\ndispute,1,1,\n";
+        let config = EngineConfig::builder().on_error(OnError::Fail).sort_by_timestamp(true).build();
+
+        let account_map = process_transactions(input.as_bytes(), &config).unwrap();
+        assert_eq!(3.0, account_map.accounts[&ClientId(1)].held());
+    }
+
+    #[test]
+    fn test_locked_only() {
+        let input = "type,client,tx,amount\ndeposit,1,1,2.0\ndeposit,2,2,3.0\ndispute,2,2,\nchargeback,2,2,\n";
+        let options = OutputOptions{locked_only: true, ..OutputOptions::default()};
+
+        let account_map = process_transactions(input.as_bytes(), &fail_fast()).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_accounts(account_map, &mut output, &options).unwrap();
+
+        assert_eq!("client,available,held,total,locked\n2,0.0,0.0,0.0,true\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_decimal_separator() {
+        let input = "type,client,tx,amount\ndeposit,1,1,1.2345\n";
+        let options = OutputOptions{delimiter: b';', decimal_separator: ',', ..OutputOptions::default()};
+
+        let account_map = process_transactions(input.as_bytes(), &fail_fast()).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_accounts(account_map, &mut output, &options).unwrap();
+
+        assert_eq!("client;available;held;total;locked\n1;1,2345;0,0;1,2345;false\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_decimal_separator_collision() {
+        let options = OutputOptions{decimal_separator: ',', ..OutputOptions::default()};
+        let mut output: Vec<u8> = Vec::new();
+
+        let result = write_accounts(AccountMap::new(), &mut output, &options);
+        assert!(matches!(result, Err(EngineError::InvalidOptions(_))));
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_quote_style() {
+        let input = "type,client,tx,amount\ndeposit,1,1,1.5\n";
+        let expected = vec![
+            (QuoteStyle::Always, "\"client\",\"available\",\"held\",\"total\",\"locked\"\n\"1\",\"1.5\",\"0.0\",\"1.5\",\"false\"\n"),
+            (QuoteStyle::Necessary, "client,available,held,total,locked\n1,1.5,0.0,1.5,false\n"),
+            (QuoteStyle::Never, "client,available,held,total,locked\n1,1.5,0.0,1.5,false\n")
+        ];
+
+        for (quote_style, output_csv) in expected {
+            let options = OutputOptions{quote_style, ..OutputOptions::default()};
+            let account_map = process_transactions(input.as_bytes(), &fail_fast()).unwrap();
+            let mut output: Vec<u8> = Vec::new();
+            write_accounts(account_map, &mut output, &options).unwrap();
+            assert_eq!(output_csv, String::from_utf8(output).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_process_iter() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(2), Some(3.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(3), Some(2.0)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(2), None)
+        ];
+        let account_map = process_iter(transactions);
+
+        assert_eq!(3.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(3.0, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(0.0, account_map.accounts[&ClientId(2)].available());
+        assert_eq!(3.0, account_map.accounts[&ClientId(2)].held());
+        assert_eq!(4, account_map.total_transaction_log.len());
+    }
+
+    #[test]
+    fn test_currency_scales() {
+        let scales = vec![("JPY".to_string(), 0), ("BTC".to_string(), 8)].into_iter().collect();
+        let config = EngineConfig::builder().on_error(OnError::Fail).currency_scales(scales).build();
+
+        let jpy = "type,client,tx,amount,currency\ndeposit,1,1,1.5,JPY\n";
+        let result = process_transactions(jpy.as_bytes(), &config);
+        assert!(matches!(result, Err(EngineError::Parse{line: 2, ..})));
+
+        let btc = "type,client,tx,amount,currency\ndeposit,1,1,0.12345678,BTC\n";
+        let account_map = process_transactions(btc.as_bytes(), &config).unwrap();
+        assert_eq!(0.12345678, account_map.accounts[&ClientId(1)].available());
+
+        // without a currency the default scale of four decimal places applies
+        let default_scale = "type,client,tx,amount,currency\ndeposit,1,1,0.12345678,\n";
+        let result = process_transactions(default_scale.as_bytes(), &config);
+        assert!(matches!(result, Err(EngineError::Parse{line: 2, ..})));
+    }
+
+    #[test]
+    fn test_currency_scales_kept_by_account() {
+        let scales = vec![("JPY".to_string(), 0), ("BTC".to_string(), 8)].into_iter().collect();
+        let config = EngineConfig::builder().round_each_op(true).currency_scales(scales).build();
+        let input = "type,client,tx,amount,currency\ndeposit,1,1,0.00000001,BTC\ndeposit,2,2,250,JPY\ndeposit,3,3,1.5,\ndispute,2,2,,\n";
+
+        let account_map = process_transactions(input.as_bytes(), &config).unwrap();
+        // rounding at the default scale of four places would take the satoshi to zero
+        assert_eq!(0.00000001, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(Some(8), account_map.accounts[&ClientId(1)].scale);
+
+        let mut output: Vec<u8> = Vec::new();
+        write_accounts(account_map, &mut output, &OutputOptions::default()).unwrap();
+        assert_eq!("client,available,held,total,locked\n1,0.00000001,0.00000000,0.00000001,false\n2,0,250,250,false\n3,1.5,0.0,1.5,false\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_column_order() {
+        let input = "type,client,tx,amount\ndeposit,1,1,5.0\ndeposit,1,2,1.0\ndispute,1,1,\n";
+        let column_order = vec![Column::Client, Column::Total, Column::Available, Column::Held, Column::Locked];
+        let options = OutputOptions{column_order: Some(column_order), ..OutputOptions::default()};
+
+        let account_map = process_transactions(input.as_bytes(), &fail_fast()).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_accounts(account_map, &mut output, &options).unwrap();
+
+        assert_eq!("client,total,available,held,locked\n1,6.0,1.0,5.0,false\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_column_order_repeated() {
+        let options = OutputOptions{column_order: Some(vec![Column::Client, Column::Total, Column::Total]), ..OutputOptions::default()};
+        let mut output: Vec<u8> = Vec::new();
+
+        let result = write_accounts(AccountMap::new(), &mut output, &options);
+        assert!(matches!(result, Err(EngineError::InvalidOptions(_))));
+        assert!("avail".parse::<Column>().is_err());
+    }
+
+    #[test]
+    fn test_field_count_strict() {
+        let short_row = "type,client,tx,amount\ndeposit,1,1,5.0\ndispute,1,1\n";
+        let result = process_transactions(short_row.as_bytes(), &fail_fast());
+        assert!(matches!(result, Err(EngineError::Parse{line: 3, ref message}) if message == "expected 4 fields but found 3"));
+
+        let long_row = "type,client,tx,amount\ndeposit,1,1,5.0\ndeposit,1,2,1.0,extra\n";
+        let result = process_transactions(long_row.as_bytes(), &fail_fast());
+        assert!(matches!(result, Err(EngineError::Parse{line: 3, ref message}) if message == "expected 4 fields but found 5"));
+    }
+
+    #[test]
+    fn test_field_count_flexible() {
+        let config = EngineConfig::builder().on_error(OnError::Fail).flexible(true).build();
+
+        let short_row = "type,client,tx,amount\ndeposit,1,1,5.0\ndispute,1,1\n";
+        let account_map = process_transactions(short_row.as_bytes(), &config).unwrap();
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].held());
+
+        let long_row = "type,client,tx,amount\ndeposit,1,1,5.0\ndeposit,1,2,1.0,extra\n";
+        let account_map = process_transactions(long_row.as_bytes(), &config).unwrap();
+        assert_eq!(6.0, account_map.accounts[&ClientId(1)].available());
+
+        // a short row still fails if it is missing a field the transaction needs
+        let missing_tx = "type,client,tx,amount\ndeposit,1\n";
+        let result = process_transactions(missing_tx.as_bytes(), &config);
+        assert!(matches!(result, Err(EngineError::Parse{line: 2, ..})));
+    }
+
+    #[test]
+    fn test_process_bytes() {
+        let data: Vec<u8> = b"type,client,tx,amount\ndeposit,1,1,3.0\nwithdrawal,1,2,1.0\ndeposit,2,3,1.5\n".to_vec();
+        let account_map = process_bytes(&data).unwrap();
+
+        assert_eq!(2.0, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(1.5, account_map.accounts[&ClientId(2)].total());
+        assert!(account_map.total_error_transaction_log.is_empty());
+    }
+
+    /// Accepts a number of lines and fails every write after them, standing in for a disk filling up.
+    struct FailingWriter {
+        written: Vec<u8>,
+        lines_allowed: usize
+    }
+
+    impl Write for FailingWriter {
+
+        fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+            let mut accepted = 0;
+            while self.lines_allowed > 0 && accepted < buffer.len() {
+                if buffer[accepted] == b'\n' {
+                    self.lines_allowed -= 1;
+                }
+                accepted += 1;
+            }
+            if accepted == 0 {
+                return Err(std::io::Error::other("no space left"))
+            }
+            self.written.extend_from_slice(&buffer[..accepted]);
+            return Ok(accepted)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            return Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_accounts_flushes_on_error() {
+        let input = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,2,2,2.0\ndeposit,3,3,3.0\n";
+        let account_map = process_bytes(input.as_bytes()).unwrap();
+        let mut output = FailingWriter{written: Vec::new(), lines_allowed: 3};
+
+        let result = write_accounts(account_map, &mut output, &OutputOptions::default());
+        assert!(matches!(result, Err(EngineError::Io(_)) | Err(EngineError::Csv(_))));
+        assert_eq!("client,available,held,total,locked\n1,1.0,0.0,1.0,false\n2,2.0,0.0,2.0,false\n", String::from_utf8(output.written).unwrap());
+    }
+
+    #[test]
+    fn test_comment_lines() {
+        let input = "# fixture for a dispute\ntype,client,tx,amount\ndeposit,1,1,5.0\n# the dispute below holds the deposit\ndispute,1,1,\n#deposit,1,2,9.0\n";
+        let config = EngineConfig::builder().on_error(OnError::Fail).allow_comments(true).build();
+
+        let account_map = process_transactions(input.as_bytes(), &config).unwrap();
+        assert_eq!(2, account_map.total_transaction_log.len());
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].held());
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].total());
+
+        // without the option the comment before the header is read as the header
+        assert!(process_transactions(input.as_bytes(), &fail_fast()).is_err());
+    }
+
+    #[test]
+    fn test_non_numeric_field_message() {
+        let input = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,1,abc,1.0\n";
+        let result = process_transactions(input.as_bytes(), &fail_fast());
+
+        match result {
+            Err(error) => {assert_eq!("line 3: column 'tx' value 'abc' is not an integer", error.to_string())},
+            Ok(_) => {panic!("the row with a non-numeric tx should fail")}
+        }
+    }
+
+    #[test]
+    fn test_max_accounts() {
+        let config = EngineConfig::builder().max_accounts(Some(2)).build();
+
+        let two_clients = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,2,2,1.0\ndeposit,1,3,1.0\n";
+        assert_eq!(2, process_transactions(two_clients.as_bytes(), &config).unwrap().accounts.len());
+
+        let three_clients = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,2,2,1.0\ndeposit,3,3,1.0\n";
+        let result = process_transactions(three_clients.as_bytes(), &config);
+        assert!(matches!(result, Err(EngineError::TooManyAccounts(2))));
+    }
+
+    #[test]
+    fn test_process_transactions_with_acks() {
+        let input = "type,client,tx,amount\ndeposit,1,1,2.0\nwithdrawal,1,2,5.0\ndeposit,one,4,1.0\ndeposit,2,3,1.0\ndeposit,3,5,1.0\n";
+        let mut acks: Vec<u8> = Vec::new();
+        let config = EngineConfig::builder().client_filter(Some(ClientFilter::Exclude(HashSet::from([ClientId(3)])))).build();
+
+        let account_map = process_transactions_with_acks(input.as_bytes(), &config, &mut acks).unwrap();
+
+        // the bad row has no transaction ID so it is acknowledged by its line
+        assert_eq!("1,accepted\n2,rejected\n4,parse_error\n3,accepted\n5,filtered\n", String::from_utf8(acks).unwrap());
+        assert_eq!(2, account_map.accounts.len());
+    }
+
+    #[test]
+    fn test_auto_detect() {
+        let config = EngineConfig::builder().auto_detect(true).build();
+        let headerless = "deposit;1;1;3.0\ndeposit;2;2;1.0\nwithdrawal;1;3;1.0\n";
+
+        let account_map = process_transactions(headerless.as_bytes(), &config).unwrap();
+        assert_eq!(2.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(1.0, account_map.accounts[&ClientId(2)].available());
+
+        // without detection every row fails to parse as a single comma separated field
+        let account_map = process_transactions(headerless.as_bytes(), &EngineConfig::default()).unwrap();
+        assert!(account_map.accounts.is_empty());
+    }
+
+    #[test]
+    fn test_strict_schema() {
+        let input = "type,client,tx,amount,note\ndeposit,1,1,1.0,hello\n";
+
+        let account_map = process_transactions(input.as_bytes(), &EngineConfig::default()).unwrap();
+        assert_eq!(1.0, account_map.accounts[&ClientId(1)].total());
+
+        let config = EngineConfig::builder().strict_schema(true).build();
+        match process_transactions(input.as_bytes(), &config) {
+            Err(EngineError::UnknownColumns(columns)) => {assert_eq!(vec!["note".to_string()], columns)},
+            other => {panic!("expected unknown columns, got {:?}", other.map(|_| ()))}
+        }
+
+        let known = "type,client,tx,amount,timestamp,currency\ndeposit,1,1,1.0,5,USD\n";
+        assert!(process_transactions(known.as_bytes(), &config).is_ok());
+    }
+
+    #[test]
+    fn test_locked_by_tx_column() {
+        let input = "type,client,tx,amount\ndeposit,1,1,5.0\ndeposit,2,2,3.0\ndispute,1,1,\nchargeback,1,1,\n";
+        let account_map = process_transactions(input.as_bytes(), &EngineConfig::default()).unwrap();
+        let options = OutputOptions{locked_by_tx: true, ..OutputOptions::default()};
+        let mut output: Vec<u8> = Vec::new();
+
+        write_accounts(account_map, &mut output, &options).unwrap();
+
+        assert_eq!("client,available,held,total,locked,locked_by_tx\n1,0.0,0.0,0.0,true,1\n2,3.0,0.0,3.0,false,\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_amount_format() {
+        let dollars = EngineConfig::builder()
+            .amount_format(Some(AmountFormat{currency_symbol: Some("$".to_string()), thousands_separator: Some(','), decimal_separator: '.'}))
+            .build();
+        let input = "type,client,tx,amount\ndeposit,1,1,\"$1,000.50\"\nwithdrawal,1,2,$0.50\n";
+        assert_eq!(1000.0, process_transactions(input.as_bytes(), &dollars).unwrap().accounts[&ClientId(1)].available());
+        // without the format the symbol makes the amounts bad rows
+        assert!(process_transactions(input.as_bytes(), &EngineConfig::default()).unwrap().accounts.is_empty());
+
+        let european = EngineConfig::builder()
+            .input_delimiter(b';')
+            .amount_format(Some(AmountFormat{currency_symbol: None, thousands_separator: Some(' '), decimal_separator: ','}))
+            .build();
+        let input = "type;client;tx;amount\ndeposit;1;1;1 000,50\ndispute;1;1;\n";
+        let account_map = process_transactions(input.as_bytes(), &european).unwrap();
+        assert_eq!(1000.5, account_map.accounts[&ClientId(1)].held());
+    }
+
+    #[test]
+    fn test_no_log() {
+        let mut input = String::from("type,client,tx,amount\n");
+        for tx in 1..=10_000 {
+            input.push_str(&format!("deposit,{},{},1.5\n", tx % 10, tx));
+        }
+        input.push_str("withdrawal,1,10001,0.5\ndispute,1,1,\n");
+        let config = EngineConfig::builder().no_log(true).build();
+
+        let account_map = process_transactions(input.as_bytes(), &config).unwrap();
+        assert_eq!(10, account_map.accounts.len());
+        assert_eq!(1499.5, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(0.0, account_map.accounts[&ClientId(1)].held());
+        assert_eq!(1500.0, account_map.accounts[&ClientId(2)].total());
+
+        // only the rejected dispute is kept, nothing else is logged
+        assert!(account_map.total_transaction_log.is_empty());
+        assert_eq!(10_001, account_map.accepted_count);
+        assert!(account_map.accounts.values().all(|account| account.transaction_log.is_empty()));
+        assert_eq!(0, account_map.used_tx_ids.len());
+        assert_eq!(1, account_map.total_error_transaction_log.len());
+        assert_eq!(TransactionError::LogDisabled, account_map.total_error_transaction_log[0].1);
+    }
+
+    #[test]
+    fn test_client_filter() {
+        let input = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,2,2,2.0\ndeposit,3,3,3.0\nwithdrawal,2,4,0.5\ndispute,3,3,\n";
+
+        let config = EngineConfig::builder().client_filter(Some(ClientFilter::Include(HashSet::from([ClientId(1), ClientId(3)])))).build();
+        let included = process_transactions(input.as_bytes(), &config).unwrap();
+        let mut clients: Vec<ClientId> = included.accounts.keys().cloned().collect();
+        clients.sort();
+        assert_eq!(vec![ClientId(1), ClientId(3)], clients);
+        assert_eq!(3.0, included.accounts[&ClientId(3)].held());
+        assert_eq!(3, included.accepted_count);
+        assert!(included.total_error_transaction_log.is_empty());
+
+        let config = EngineConfig::builder().client_filter(Some(ClientFilter::Exclude(HashSet::from([ClientId(3)])))).build();
+        let excluded = process_transactions(input.as_bytes(), &config).unwrap();
+        let mut clients: Vec<ClientId> = excluded.accounts.keys().cloned().collect();
+        clients.sort();
+        assert_eq!(vec![ClientId(1), ClientId(2)], clients);
+        assert_eq!(1.5, excluded.accounts[&ClientId(2)].available());
+        assert_eq!(3, excluded.accepted_count);
+        assert!(excluded.total_error_transaction_log.is_empty());
+    }
+
+    #[test]
+    fn test_process_files_namespaced() {
+        let first = "type,client,tx,amount\ndeposit,1,1,5.0\ndispute,1,1,\n";
+        let second = "type,client,tx,amount\ndeposit,1,1,2.0\nwithdrawal,1,2,1.0\n";
+
+        // without namespacing the second deposit reuses tx 1 and is rejected, leaving nothing to withdraw
+        let (shared, _) = process_files(vec![first.as_bytes(), second.as_bytes()], &EngineConfig::default()).unwrap();
+        assert_eq!(5.0, shared.accounts[&ClientId(1)].total());
+        assert_eq!(2, shared.total_error_transaction_log.len());
+
+        let config = EngineConfig::builder().tx_namespace_per_file(true).build();
+        let (account_map, namespace) = process_files(vec![first.as_bytes(), second.as_bytes()], &config).unwrap();
+        let account = &account_map.accounts[&ClientId(1)];
+        assert_eq!(5.0, account.held());
+        assert_eq!(1.0, account.available());
+        assert_eq!(6.0, account.total());
+        assert!(account_map.total_error_transaction_log.is_empty());
+
+        // only the dispute from the first file's tx 1 is open
+        let third = "type,client,tx,amount\nresolve,1,1,\n";
+        let (account_map, _) = process_files(vec![first.as_bytes(), second.as_bytes(), third.as_bytes()], &config).unwrap();
+        assert_eq!(5.0, account_map.accounts[&ClientId(1)].held());
+        assert_eq!(Some((1, TxId(1))), namespace.origin(account_map.total_transaction_log[2].tx));
+    }
+
+    #[test]
+    fn test_negative_total_output() {
+        // the chargeback of a deposit that has mostly been withdrawn leaves a total of -4
+        let input = "type,client,tx,amount\ndeposit,1,1,5.0\nwithdrawal,1,2,4.0\ndeposit,2,3,1.0\ndispute,1,1,\nchargeback,1,1,\n";
+        let write = |negative_total: NegativeTotalOutput| {
+            let account_map = process_transactions(input.as_bytes(), &fail_fast()).unwrap();
+            let mut output: Vec<u8> = Vec::new();
+            write_accounts(account_map, &mut output, &OutputOptions{negative_total, ..OutputOptions::default()}).unwrap();
+            return String::from_utf8(output).unwrap()
+        };
+
+        assert_eq!("client,available,held,total,locked\n1,-4.0,0.0,-4.0,true\n2,1.0,0.0,1.0,false\n", write(NegativeTotalOutput::Leave));
+        assert_eq!("client,available,held,total,locked\n1,-4.0,0.0,0.0,true\n2,1.0,0.0,1.0,false\n", write(NegativeTotalOutput::Clamp));
+        assert_eq!("client,available,held,total,locked,overdrawn\n1,-4.0,0.0,-4.0,true,true\n2,1.0,0.0,1.0,false,false\n", write(NegativeTotalOutput::Flag));
+    }
+
+    #[test]
+    fn test_parse_rows() {
+        let input = "type,client,tx,amount\ndeposit,1,1,2.0\ndeposit,one,2,1.0\nwithdrawal,1,3,0.5\n";
+
+        let rows = parse_rows(input.as_bytes(), &EngineConfig::default()).unwrap();
+
+        assert_eq!(3, rows.len());
+        assert!(matches!(rows[0], RowResult::Parsed(Transaction{transaction_type: DEPOSIT, client: ClientId(1), tx: TxId(1), ..})));
+        assert!(matches!(rows[1], RowResult::ParseError{line: 3, ..}), "{:?}", rows[1]);
+        assert!(matches!(rows[2], RowResult::Parsed(Transaction{transaction_type: WITHDRAWAL, client: ClientId(1), tx: TxId(3), ..})));
+
+        // the bad row is only skipped once the rows are applied
+        let account_map = process_transactions(input.as_bytes(), &EngineConfig::default()).unwrap();
+        assert_eq!(1.5, account_map.accounts.get(&ClientId(1)).unwrap().available());
+        assert_eq!(vec![3], account_map.skipped_rows.iter().map(|(line, _)| *line).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        let reported: Arc<Mutex<Vec<usize>>> = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&reported);
+        let config = EngineConfig::builder().progress_interval(Some(2)).on_progress(move |rows_read| received.lock().unwrap().push(rows_read)).build();
+        let input = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,1,2,1.0\ndeposit,1,3,1.0\ndeposit,1,4,1.0\ndeposit,1,5,1.0\n";
+
+        process_transactions(input.as_bytes(), &config).unwrap();
+        assert_eq!(vec![2, 4], *reported.lock().unwrap());
+    }
+
+    #[test]
+    fn test_negative_ids_rejected() {
+        let input = "type,client,tx,amount
+deposit,-1,1,2.0
+deposit,1,-2,1.0
+deposit,1,3,0.5
+";
+
+        let rows = parse_rows(input.as_bytes(), &EngineConfig::default()).unwrap();
+
+        // client and transaction IDs are unsigned so a negative one is a malformed row
+        assert!(matches!(rows[0], RowResult::ParseError{line: 2, ..}), "{:?}", rows[0]);
+        assert!(matches!(rows[1], RowResult::ParseError{line: 3, ..}), "{:?}", rows[1]);
+        assert!(matches!(rows[2], RowResult::Parsed(Transaction{client: ClientId(1), tx: TxId(3), ..})));
+    }
+
+    /// Gives out a header and one row and then fails, like a file on a disk that goes away part way through.
+    struct FailingReader {
+        data: std::io::Cursor<&'static [u8]>
+    }
+
+    impl std::io::Read for FailingReader {
+
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.data.read(buf)?;
+            if read == 0 {
+                return Err(std::io::Error::other("disk gone"))
+            }
+            return Ok(read)
+        }
+    }
+
+    #[test]
+    fn test_read_error_is_fatal() {
+        let failing = || FailingReader{data: std::io::Cursor::new(b"type,client,tx,amount\ndeposit,1,1,2.0\n")};
+
+        // an error with no line is not a bad row that can be skipped
+        assert!(matches!(parse_rows(failing(), &EngineConfig::default()), Err(EngineError::Csv(_))));
+        let config = EngineConfig::builder().on_error(OnError::Continue).build();
+        assert!(matches!(process_transactions(failing(), &config), Err(EngineError::Csv(_))));
+    }
+}
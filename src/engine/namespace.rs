@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use super::super::transactions::ids::TxId;
+
+
+/// This struct is responsible for giving the transaction IDs of several input files their own space, so the same ID in two files
+/// refers to two different transactions. Each (file, tx) pair is mapped to an internal ID the accounts are keyed on.
+///
+/// # Attributes
+/// * ids (HashMap<(usize, TxId), TxId>): the internal ID given to each transaction ID of each file
+/// * origins (Vec<(usize, TxId)>): the file and transaction ID behind each internal ID, the internal ID minus one is the index
+#[derive(Debug, Clone, Default)]
+pub struct TxNamespace {
+    ids: HashMap<(usize, TxId), TxId>,
+    origins: Vec<(usize, TxId)>
+}
+
+impl TxNamespace {
+
+    /// The constructor for the TxNamespace struct.
+    ///
+    /// # Returns
+    /// * (TxNamespace): a namespace with no IDs handed out
+    pub fn new() -> TxNamespace {
+        return TxNamespace::default()
+    }
+
+    /// Gets the internal ID of a transaction ID from a file, handing out the next one the first time the pair is seen.
+    ///
+    /// # Arguments
+    /// * file (usize): the index of the input file
+    /// * tx (TxId): the transaction ID as written in the file
+    ///
+    /// # Returns
+    /// * (TxId): the internal ID, the same for every row of the file using the transaction ID
+    pub fn resolve(&mut self, file: usize, tx: TxId) -> TxId {
+        if let Some(id) = self.ids.get(&(file, tx)) {
+            return *id
+        }
+        self.origins.push((file, tx));
+        let id = TxId(self.origins.len() as u32);
+        self.ids.insert((file, tx), id);
+        return id
+    }
+
+    /// Finds the file and transaction ID an internal ID was handed out for, to report a transaction as it was written.
+    ///
+    /// # Arguments
+    /// * id (TxId): the internal ID
+    ///
+    /// # Returns
+    /// * (Option<(usize, TxId)>): the index of the file and the transaction ID in it, None if the ID was never handed out
+    pub fn origin(&self, id: TxId) -> Option<(usize, TxId)> {
+        let index = usize::try_from(id.0).ok()?.checked_sub(1)?;
+        return self.origins.get(index).copied()
+    }
+}
+
+
+#[cfg(test)]
+mod namespace_tests {
+
+    use super::TxNamespace;
+    use super::super::super::transactions::ids::TxId;
+
+    #[test]
+    fn test_resolve() {
+        let mut namespace = TxNamespace::new();
+
+        assert_eq!(TxId(1), namespace.resolve(0, TxId(1)));
+        assert_eq!(TxId(2), namespace.resolve(1, TxId(1)));
+        assert_eq!(TxId(1), namespace.resolve(0, TxId(1)));
+        assert_eq!(TxId(3), namespace.resolve(0, TxId(7)));
+
+        assert_eq!(Some((1, TxId(1))), namespace.origin(TxId(2)));
+        assert_eq!(Some((0, TxId(7))), namespace.origin(TxId(3)));
+        assert_eq!(None, namespace.origin(TxId(0)));
+        assert_eq!(None, namespace.origin(TxId(4)));
+    }
+}
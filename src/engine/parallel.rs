@@ -0,0 +1,140 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::thread;
+use std::time::Duration;
+
+use super::config::EngineConfig;
+use super::super::accounts::account_map::AccountMap;
+use super::super::transactions::transaction::Transaction;
+
+
+/// This struct is responsible for holding the outcome of processing transactions across worker threads.
+///
+/// # Attributes
+/// * account_map (AccountMap): the accounts of every partition merged together
+/// * max_queue_depth (usize): the most transactions that were waiting in any one worker queue at once
+pub struct ParallelOutcome {
+    pub account_map: AccountMap,
+    pub max_queue_depth: usize
+}
+
+
+/// Applies transactions on worker threads, each client always going to the same worker so its transactions keep their order.
+/// The queue to each worker holds at most config.worker_queue_bound transactions, once it is full the reader blocks until the
+/// worker catches up so a fast input can not grow the queues without limit.
+///
+/// Duplicate transaction IDs are only detected within a partition, so TxIdScope::Global is only exact
+/// for IDs used by clients on the same worker, and the logs of the merged map are grouped by worker.
+///
+/// # Arguments
+/// * transactions (I): the transactions to apply
+/// * config (&EngineConfig): the policies applied to the transactions and the number of workers
+///
+/// # Returns
+/// * (ParallelOutcome): the merged accounts and the deepest a queue got
+pub fn process_parallel<I: IntoIterator<Item = Transaction>>(transactions: I, config: &EngineConfig) -> ParallelOutcome {
+    return process_partitioned(transactions, config, None)
+}
+
+
+/// The partitioned processing behind process_parallel.
+///
+/// # Arguments
+/// * transactions (I): the transactions to apply
+/// * config (&EngineConfig): the policies applied to the transactions and the number of workers
+/// * worker_delay (Option<Duration>): a pause before each transaction is applied, used to stand in for a slow worker
+///
+/// # Returns
+/// * (ParallelOutcome): the merged accounts and the deepest a queue got
+fn process_partitioned<I: IntoIterator<Item = Transaction>>(transactions: I, config: &EngineConfig, worker_delay: Option<Duration>) -> ParallelOutcome {
+    let workers = config.worker_threads.max(1);
+    let max_queue_depth = AtomicUsize::new(0);
+    let depths: Vec<AtomicUsize> = (0..workers).map(|_| AtomicUsize::new(0)).collect();
+
+    let partitions: Vec<AccountMap> = thread::scope(|scope| {
+        let mut senders: Vec<SyncSender<Transaction>> = Vec::new();
+        let mut handles = Vec::new();
+
+        for depth in depths.iter() {
+            let (sender, receiver) = sync_channel::<Transaction>(config.worker_queue_bound);
+            senders.push(sender);
+            let worker_config = config.clone();
+            handles.push(scope.spawn(move || {
+                let mut account_map = AccountMap::new().with_config(worker_config);
+                for transaction in receiver {
+                    depth.fetch_sub(1, Ordering::SeqCst);
+                    if let Some(delay) = worker_delay {
+                        thread::sleep(delay);
+                    }
+                    account_map = account_map.add_transaction(transaction);
+                }
+                return account_map
+            }));
+        }
+
+        for transaction in transactions {
+            let worker = (transaction.client.0 % workers as u32) as usize;
+            // counted before sending so the transaction the reader is blocked on is included
+            let depth = depths[worker].fetch_add(1, Ordering::SeqCst) + 1;
+            max_queue_depth.fetch_max(depth, Ordering::SeqCst);
+            // a worker only stops once its sender is dropped so the send can not fail
+            senders[worker].send(transaction).unwrap();
+        }
+        drop(senders);
+        return handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    let mut merged = AccountMap::new().with_config(config.clone());
+    for partition in partitions {
+        merged.merge(partition);
+    }
+    return ParallelOutcome{account_map: merged, max_queue_depth: max_queue_depth.into_inner()}
+}
+
+
+#[cfg(test)]
+mod parallel_tests {
+
+    use std::time::Duration;
+
+    use super::{process_parallel, process_partitioned};
+    use super::super::config::EngineConfig;
+    use super::super::super::transactions::transaction::Transaction;
+    use super::super::super::transactions::enums::TransactionType::{DEPOSIT, DISPUTE, WITHDRAWAL};
+    use super::super::super::transactions::ids::{ClientId, TxId};
+
+    #[test]
+    fn test_process_parallel() {
+        let config = EngineConfig::builder().worker_threads(3).build();
+        let mut transactions: Vec<Transaction> = Vec::new();
+        for client in 1..=6 {
+            transactions.push(Transaction::new(DEPOSIT,    ClientId(client), TxId(1), Some(10.0)));
+            transactions.push(Transaction::new(WITHDRAWAL, ClientId(client), TxId(2), Some(client as f32)));
+            transactions.push(Transaction::new(DISPUTE,    ClientId(client), TxId(1), None));
+        }
+
+        let outcome = process_parallel(transactions, &config);
+
+        assert_eq!(6, outcome.account_map.accounts.len());
+        for client in 1..=6 {
+            let account = &outcome.account_map.accounts[&ClientId(client)];
+            assert_eq!(-(client as f32), account.available());
+            assert_eq!(10.0, account.held());
+        }
+        assert_eq!(18, outcome.account_map.total_transaction_log.len());
+    }
+
+    #[test]
+    fn test_bounded_queue() {
+        let config = EngineConfig::builder().worker_threads(2).worker_queue_bound(2).build();
+        let transactions: Vec<Transaction> = (1..=200).map(|tx| Transaction::new(DEPOSIT, ClientId(tx % 2), TxId(tx), Some(1.0))).collect();
+
+        let outcome = process_partitioned(transactions, &config, Some(Duration::from_micros(200)));
+
+        // the reader blocks on a full queue, on top of the bound the count can only include the transaction being sent
+        // and one the worker has taken but not yet counted off, without the bound it would reach 100
+        assert!(outcome.max_queue_depth <= 4, "max queue depth was {}", outcome.max_queue_depth);
+        assert_eq!(100.0, outcome.account_map.accounts[&ClientId(0)].total());
+        assert_eq!(100.0, outcome.account_map.accounts[&ClientId(1)].total());
+    }
+}
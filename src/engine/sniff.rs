@@ -0,0 +1,122 @@
+/// The columns assumed for a file without a header row.
+pub const DEFAULT_HEADERS: [&str; 4] = ["type", "client", "tx", "amount"];
+
+/// The delimiters tried when sniffing, the first is the fallback when the sample is ambiguous.
+const CANDIDATE_DELIMITERS: [u8; 3] = [b',', b';', b'\t'];
+
+/// The number of lines from the start of the input used to guess the format.
+const SNIFF_LINES: usize = 5;
+
+
+/// This struct is responsible for describing the layout of a CSV input.
+///
+/// # Attributes
+/// * delimiter (u8): the character separating the fields
+/// * has_headers (bool): if the first row names the columns
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct InputFormat {
+    pub delimiter: u8,
+    pub has_headers: bool
+}
+
+impl Default for InputFormat {
+
+    fn default() -> Self {
+        return InputFormat{delimiter: b',', has_headers: true}
+    }
+}
+
+
+/// Guesses the delimiter and if there is a header row from the first lines of an input.
+/// A delimiter is only picked if it splits every sampled line into the same number of fields,
+/// when several do the one giving the most fields wins and a tie falls back to a comma.
+///
+/// # Arguments
+/// * sample (&[u8]): the start of the input, a trailing partial line is ignored
+///
+/// # Returns
+/// * (InputFormat): the guessed format, a comma with headers if nothing better fits
+pub fn sniff_format(sample: &[u8]) -> InputFormat {
+    let text = String::from_utf8_lossy(sample);
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    if lines.len() > 1 {
+        // the last piece is either empty or a line cut off by the end of the sample
+        lines.pop();
+    }
+    let lines: Vec<&str> = lines.into_iter().map(|line| line.trim_end_matches('\r')).filter(|line| !line.is_empty()).take(SNIFF_LINES).collect();
+    if lines.is_empty() {
+        return InputFormat::default()
+    }
+
+    let mut best: Option<(u8, usize)> = None;
+    let mut tied = false;
+    for delimiter in CANDIDATE_DELIMITERS {
+        let count = lines[0].bytes().filter(|byte| *byte == delimiter).count();
+        if count == 0 || lines.iter().any(|line| line.bytes().filter(|byte| *byte == delimiter).count() != count) {
+            continue
+        }
+        match best {
+            Some((_, best_count)) if best_count > count => {},
+            Some((_, best_count)) if best_count == count => {tied = true},
+            _ => {
+                best = Some((delimiter, count));
+                tied = false;
+            }
+        }
+    }
+    let delimiter = match best {
+        Some((delimiter, _)) if !tied => delimiter,
+        _ => {return InputFormat::default()}
+    };
+
+    // a first row whose client and tx fields are numbers is data rather than a header
+    let fields: Vec<&str> = lines[0].split(delimiter as char).map(|field| field.trim()).collect();
+    let is_data = fields.len() >= 3 && fields[1].parse::<i64>().is_ok() && fields[2].parse::<i64>().is_ok();
+    return InputFormat{delimiter, has_headers: !is_data}
+}
+
+
+#[cfg(test)]
+mod sniff_tests {
+
+    use super::{sniff_format, InputFormat};
+
+    #[test]
+    fn test_sniff_comma() {
+        let sample = b"type,client,tx,amount\ndeposit,1,1,1.0\nwithdrawal,1,2,0.5\n";
+        assert_eq!(InputFormat{delimiter: b',', has_headers: true}, sniff_format(sample));
+    }
+
+    #[test]
+    fn test_sniff_semicolon() {
+        // the commas used as decimal separators are consistent too but give fewer fields
+        let sample = b"type;client;tx;amount\ndeposit;1;1;1,5\nwithdrawal;1;2;0,5\n";
+        assert_eq!(InputFormat{delimiter: b';', has_headers: true}, sniff_format(sample));
+    }
+
+    #[test]
+    fn test_sniff_tab() {
+        let sample = b"type\tclient\ttx\tamount\r\ndeposit\t1\t1\t1.0\r\n";
+        assert_eq!(InputFormat{delimiter: b'\t', has_headers: true}, sniff_format(sample));
+    }
+
+    #[test]
+    fn test_sniff_headerless() {
+        let sample = b"deposit,1,1,1.0\ndeposit,2,2,2.0\ndispute,1,1,\n";
+        assert_eq!(InputFormat{delimiter: b',', has_headers: false}, sniff_format(sample));
+    }
+
+    #[test]
+    fn test_sniff_ambiguous() {
+        assert_eq!(InputFormat::default(), sniff_format(b""));
+        assert_eq!(InputFormat::default(), sniff_format(b"a,b;c\nd,e;f\n"));
+        // the line counts disagree for every delimiter
+        assert_eq!(InputFormat::default(), sniff_format(b"a;b\nc;d;e\n"));
+    }
+
+    #[test]
+    fn test_sniff_ignores_partial_line() {
+        let sample = b"type;client;tx;amount\ndeposit;1;1;1.0\ndepo";
+        assert_eq!(InputFormat{delimiter: b';', has_headers: true}, sniff_format(sample));
+    }
+}
@@ -0,0 +1,73 @@
+use serde::Serialize;
+
+use super::super::accounts::account_map::AccountMap;
+
+
+/// This struct is responsible for summarising the outcome of processing a stream of transactions.
+///
+/// # Attributes
+/// * accepted_transactions (usize): the number of transactions that were applied
+/// * rejected_transactions (usize): the number of transactions that broke the rules of their type
+/// * accounts (usize): the number of accounts
+/// * locked_accounts (usize): the number of accounts that have been locked
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct EngineStats {
+    pub accepted_transactions: usize,
+    pub rejected_transactions: usize,
+    pub accounts: usize,
+    pub locked_accounts: usize
+}
+
+impl EngineStats {
+
+    /// Gathers the stats from a processed map of accounts.
+    ///
+    /// # Arguments
+    /// * account_map (&AccountMap): the processed accounts
+    ///
+    /// # Returns
+    /// * (EngineStats): the stats for the map
+    pub fn from_map(account_map: &AccountMap) -> EngineStats {
+        return EngineStats{
+            accepted_transactions: account_map.accepted_count,
+            rejected_transactions: account_map.total_error_transaction_log.len(),
+            accounts: account_map.accounts.len(),
+            locked_accounts: account_map.accounts.values().filter(|account| account.is_locked()).count()
+        }
+    }
+
+    /// Renders the stats as JSON.
+    ///
+    /// # Returns
+    /// * (String): the stats as a JSON object
+    pub fn to_json(&self) -> String {
+        // the struct only holds numbers so serialising it can not fail
+        return serde_json::to_string(self).unwrap()
+    }
+}
+
+
+#[cfg(test)]
+mod stats_tests {
+
+    use super::EngineStats;
+    use super::super::super::accounts::account_map::AccountMap;
+    use super::super::super::transactions::transaction::Transaction;
+    use super::super::super::transactions::enums::TransactionType::{CHARGEBACK, DEPOSIT, DISPUTE, WITHDRAWAL};
+    use super::super::super::transactions::ids::{ClientId, TxId};
+
+    #[test]
+    fn test_stats() {
+        let mut account_map = AccountMap::new();
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(2.0)));
+        account_map = account_map.add_transaction(Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(5.0)));
+        account_map = account_map.add_transaction(Transaction::new(DEPOSIT,    ClientId(2), TxId(3), Some(2.0)));
+        account_map = account_map.add_transaction(Transaction::new(DISPUTE,    ClientId(2), TxId(3), None));
+        account_map = account_map.add_transaction(Transaction::new(CHARGEBACK, ClientId(2), TxId(3), None));
+
+        let stats = EngineStats::from_map(&account_map);
+
+        assert_eq!(EngineStats{accepted_transactions: 4, rejected_transactions: 1, accounts: 2, locked_accounts: 1}, stats);
+        assert_eq!("{\"accepted_transactions\":4,\"rejected_transactions\":1,\"accounts\":2,\"locked_accounts\":1}", stats.to_json());
+    }
+}
@@ -0,0 +1,56 @@
+use std::pin::pin;
+
+use futures_util::stream::{Stream, StreamExt};
+
+use super::super::accounts::log_transaction;
+use super::super::accounts::account_map::AccountMap;
+use super::super::transactions::transaction::Transaction;
+
+
+/// Applies every transaction from an async stream to a fresh map of accounts.
+///
+/// # Arguments
+/// * stream (S): the transactions, applied one at a time in the order the stream yields them
+///
+/// # Returns
+/// * (AccountMap): the accounts after the stream has ended
+pub async fn process_stream<S: Stream<Item = Transaction>>(stream: S) -> AccountMap {
+    let mut stream = pin!(stream);
+    let mut account_map = AccountMap::new();
+
+    while let Some(transaction) = stream.next().await {
+        (account_map, _) = log_transaction(Some(account_map), transaction);
+    }
+    return account_map
+}
+
+
+#[cfg(test)]
+mod stream_tests {
+
+    use futures_util::stream;
+
+    use super::process_stream;
+    use super::Transaction;
+    use super::super::super::transactions::enums::TransactionType::{DEPOSIT, DISPUTE, RESOLVE, WITHDRAWAL};
+    use super::super::super::transactions::ids::{ClientId, TxId};
+
+    #[tokio::test]
+    async fn test_process_stream() {
+        let transactions = vec![
+            Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(5.0)),
+            Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(2.0)),
+            Transaction::new(DEPOSIT,    ClientId(2), TxId(3), Some(4.0)),
+            Transaction::new(DISPUTE,    ClientId(2), TxId(3), None),
+            Transaction::new(RESOLVE,    ClientId(2), TxId(3), None)
+        ];
+
+        let account_map = process_stream(stream::iter(transactions)).await;
+
+        assert_eq!(3.0, account_map.accounts[&ClientId(1)].available());
+        assert_eq!(3.0, account_map.accounts[&ClientId(1)].total());
+        assert_eq!(4.0, account_map.accounts[&ClientId(2)].available());
+        assert_eq!(0.0, account_map.accounts[&ClientId(2)].held());
+        assert_eq!(5, account_map.total_transaction_log.len());
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+
+use super::config::{EngineConfig, OutputOptions};
+use super::{process_transactions, write_accounts};
+
+
+/// This enum is responsible for describing whether a claimed output is what the engine produces for an input.
+///
+/// # Attributes
+/// * Matches: every line of the claimed output is the one the engine writes
+/// * Diverges: the first line that differs, numbered from 1 with the header as line 1, None where one side has run out of lines
+/// * Failed: the input could not be processed or the claimed output could not be read
+#[derive(Debug, PartialEq, Clone)]
+pub enum VerifyResult {
+    Matches,
+    Diverges { line: usize, expected: Option<String>, claimed: Option<String> },
+    Failed(String)
+}
+
+impl fmt::Display for VerifyResult {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |line: &Option<String>| line.clone().unwrap_or_else(|| "<end of output>".to_string());
+        match self {
+            VerifyResult::Matches => {return write!(f, "output matches")},
+            VerifyResult::Diverges { line, expected, claimed } => {
+                return write!(f, "output diverges at line {}: expected {} but the claimed output has {}", line, describe(expected), describe(claimed))
+            },
+            VerifyResult::Failed(message) => {return write!(f, "could not verify: {}", message)}
+        }
+    }
+}
+
+
+/// Re-runs the engine with the default config over an input and checks a claimed output matches what it writes.
+///
+/// # Arguments
+/// * input (impl Read): the CSV transactions the claimed output was produced from
+/// * claimed_output (impl Read): the account CSV to check
+///
+/// # Returns
+/// * (VerifyResult): if the outputs match, or the first line where they do not
+pub fn verify(input: impl Read, claimed_output: impl Read) -> VerifyResult {
+    return verify_with_config(input, claimed_output, &EngineConfig::default(), &OutputOptions::default())
+}
+
+
+/// Re-runs the engine over an input and checks a claimed output matches what it writes, for outputs made with other options.
+///
+/// # Arguments
+/// * input (impl Read): the CSV transactions the claimed output was produced from
+/// * claimed_output (impl Read): the account CSV to check
+/// * config (&EngineConfig): the config the input is processed with
+/// * options (&OutputOptions): how the expected output is written
+///
+/// # Returns
+/// * (VerifyResult): if the outputs match, or the first line where they do not
+pub fn verify_with_config(input: impl Read, claimed_output: impl Read, config: &EngineConfig, options: &OutputOptions) -> VerifyResult {
+    let mut expected: Vec<u8> = Vec::new();
+    let written = process_transactions(input, config).and_then(|account_map| write_accounts(account_map, &mut expected, options));
+    if let Err(error) = written {
+        return VerifyResult::Failed(error.to_string())
+    }
+    let claimed = match BufReader::new(claimed_output).lines().collect::<Result<Vec<String>, _>>() {
+        Ok(lines) => lines,
+        Err(error) => {return VerifyResult::Failed(error.to_string())}
+    };
+    // the output is written by this process so it is always UTF-8
+    let expected: Vec<&str> = std::str::from_utf8(&expected).unwrap_or_default().lines().collect();
+
+    for line in 0..expected.len().max(claimed.len()) {
+        let expected_line = expected.get(line).copied();
+        // a claimed output written on Windows should not diverge only on its line endings
+        let claimed_line = claimed.get(line).map(|claimed_line| claimed_line.trim_end_matches('\r'));
+        if expected_line != claimed_line {
+            return VerifyResult::Diverges{line: line + 1, expected: expected_line.map(str::to_string), claimed: claimed_line.map(str::to_string)}
+        }
+    }
+    return VerifyResult::Matches
+}
+
+
+#[cfg(test)]
+mod verify_tests {
+
+    use super::{verify, VerifyResult};
+
+    const INPUT: &str = "type,client,tx,amount\ndeposit,1,1,2.0\ndeposit,2,2,3.0\nwithdrawal,1,3,0.5\n";
+
+    #[test]
+    fn test_verify_matches() {
+        let claimed = "client,available,held,total,locked\r\n1,1.5,0.0,1.5,false\r\n2,3.0,0.0,3.0,false\r\n";
+        assert_eq!(VerifyResult::Matches, verify(INPUT.as_bytes(), claimed.as_bytes()));
+    }
+
+    #[test]
+    fn test_verify_diverges() {
+        let claimed = "client,available,held,total,locked\n1,2.0,0.0,2.0,false\n2,3.0,0.0,3.0,false\n";
+        assert_eq!(VerifyResult::Diverges{
+            line: 2,
+            expected: Some("1,1.5,0.0,1.5,false".to_string()),
+            claimed: Some("1,2.0,0.0,2.0,false".to_string())
+        }, verify(INPUT.as_bytes(), claimed.as_bytes()));
+
+        let truncated = "client,available,held,total,locked\n1,1.5,0.0,1.5,false\n";
+        assert_eq!(VerifyResult::Diverges{line: 3, expected: Some("2,3.0,0.0,3.0,false".to_string()), claimed: None}, verify(INPUT.as_bytes(), truncated.as_bytes()));
+    }
+
+    #[test]
+    fn test_verify_failed() {
+        // the claimed output is not UTF-8 so it can not be read as lines
+        let result = verify(INPUT.as_bytes(), &[0xff, 0xfe, b'\n'][..]);
+        assert!(matches!(result, VerifyResult::Failed(_)), "{:?}", result);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod data_access_layer;
+pub mod transactions;
+pub mod accounts;
+pub mod engine;
@@ -1,36 +1,370 @@
+use std::collections::HashSet;
 use std::io;
 use std::env;
+use std::fs::File;
+use std::process;
 
-use csv;
+#[cfg(feature = "interrupt")]
+use std::sync::atomic::Ordering;
 
-mod data_access_layer;
-mod transactions;
-mod accounts;
+use transactions::accounts::account_map::AccountMap;
+use transactions::engine::{check_no_negative, process_transactions_with_acks, write_accounts, write_validation_report};
+#[cfg(not(feature = "interrupt"))]
+use transactions::engine::process_transactions;
+#[cfg(feature = "interrupt")]
+use transactions::engine::{interrupt, process_transactions_until};
+#[cfg(feature = "parquet")]
+use transactions::engine::columnar;
+#[cfg(feature = "zip")]
+use transactions::engine::archive;
+use transactions::engine::config::{ClientFilter, Column, EngineConfig, NegativeTotalOutput, OnError, OutputOptions, QuoteStyle};
+use transactions::engine::errors::EngineError;
+use transactions::engine::stats::EngineStats;
+use transactions::engine::verify::{verify_with_config, VerifyResult};
+use transactions::transactions::ids::ClientId;
 
-use accounts::log_transaction;
-use accounts::account_map::AccountMap;
-use data_access_layer::schema::{TransactionSchema, AccountSchema};
 
+/// The number of rows between progress updates when --progress is given without --progress-interval.
+const DEFAULT_PROGRESS_INTERVAL: usize = 100_000;
 
-fn main() {
 
-    let args: Vec<String> = env::args().collect();
-    let file_path = &args[args.len() - 1];
+/// This enum is responsible for defining what the program does with the processed transactions.
+///
+/// # Attributes
+/// * Process: writes the state of the accounts as CSV
+/// * Validate: writes a dry-run report of accepted and rejected transactions
+/// * Stats: writes the EngineStats as JSON
+/// * Verify: re-runs the engine and checks the output in the file at the path matches it
+#[derive(Debug, PartialEq)]
+enum Command {
+    Process,
+    Validate,
+    Stats,
+    Verify(String)
+}
+
+
+/// This struct is responsible for housing the options passed on the command line.
+///
+/// # Attributes
+/// * command (Command): the subcommand to run
+/// * file_path (String): the path to the transactions file, or to the zip archive of them when zip is set
+/// * config (EngineConfig): the parsing options and policies for processing
+/// * no_negative (bool): if true the run fails when any account ends with negative available funds
+/// * strict (bool): if true the run fails when any account breaks the balance invariants
+/// * acks (bool): if true whether each transaction was accepted or rejected is written to stderr
+/// * zip (bool): if true the file is a zip archive whose CSV entries are processed in name order as one stream
+/// * parquet_path (Option<String>): where the accounts are written as Parquet instead of CSV to stdout
+/// * output (OutputOptions): how the accounts are written
+struct CliArgs {
+    command: Command,
+    file_path: String,
+    config: EngineConfig,
+    no_negative: bool,
+    strict: bool,
+    acks: bool,
+    #[cfg_attr(not(feature = "zip"), allow(dead_code))]
+    zip: bool,
+    #[cfg_attr(not(feature = "parquet"), allow(dead_code))]
+    parquet_path: Option<String>,
+    output: OutputOptions
+}
+
+
+/// Reads the value of an option that must be a single ASCII character.
+///
+/// # Arguments
+/// * value (Option<&String>): the value following the option
+/// * option (&str): the name of the option for the error message
+///
+/// # Returns
+/// * (Result<u8, String>): the character as a byte
+fn parse_single_byte(value: Option<&String>, option: &str) -> Result<u8, String> {
+    match value.map(|value| value.as_bytes()) {
+        Some([byte]) if byte.is_ascii() => {return Ok(*byte)},
+        _ => {return Err(format!("{} requires a single ASCII character", option))}
+    }
+}
+
+
+/// Parses a comma separated list of client IDs.
+///
+/// # Arguments
+/// * value (Option<&String>): the value following the option
+/// * option (&str): the name of the option for the error message
+///
+/// # Returns
+/// * (Result<HashSet<ClientId>, String>): the client IDs
+fn parse_clients(value: Option<&String>, option: &str) -> Result<HashSet<ClientId>, String> {
+    let value = value.ok_or(format!("{} requires a value", option))?;
+    return value.split(',').map(|client| {
+        client.trim().parse::<u32>().map(ClientId).map_err(|_| format!("{} expects client IDs, got '{}'", option, client))
+    }).collect()
+}
+
+
+/// Reads the subcommand, file path and options from the command line arguments.
+///
+/// # Arguments
+/// * args (&[String]): the arguments passed to the program including the program name
+///
+/// # Returns
+/// * (Result<CliArgs, String>): the parsed arguments, or a message explaining what is wrong with them
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut remaining = args.iter().skip(1).peekable();
+
+    // without a subcommand the arguments are for process to keep the original usage working
+    let command = match remaining.peek().map(|arg| arg.as_str()) {
+        Some("process") => {remaining.next(); Command::Process},
+        Some("validate") => {remaining.next(); Command::Validate},
+        Some("stats") => {remaining.next(); Command::Stats},
+        Some("verify") => {
+            remaining.next();
+            Command::Verify(remaining.next().ok_or("usage: transactions verify <claimed output> [options] <file>")?.clone())
+        },
+        _ => Command::Process
+    };
+
+    let mut file_path: Option<String> = None;
+    let mut config = EngineConfig::builder().on_progress(|rows_read| eprintln!("processed {} rows", rows_read));
+    let mut no_negative = false;
+    let mut strict = false;
+    let mut acks = false;
+    let mut zip = false;
+    let mut parquet_path: Option<String> = None;
+    let mut output = OutputOptions::default();
+
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "--on-error" => {
+                let selection = remaining.next().ok_or("--on-error requires a value")?;
+                config = config.on_error(selection.parse::<OnError>()?);
+            },
+            "--sort-by-timestamp" => {
+                config = config.sort_by_timestamp(true);
+            },
+            "--progress" => {
+                config = config.progress_interval(Some(DEFAULT_PROGRESS_INTERVAL));
+            },
+            "--progress-interval" => {
+                let interval = remaining.next().ok_or("--progress-interval requires a value")?;
+                let interval = interval.parse::<usize>().ok().filter(|interval| *interval > 0).ok_or("--progress-interval requires a positive number of rows")?;
+                config = config.progress_interval(Some(interval));
+            },
+            "--comments" => {
+                config = config.allow_comments(true);
+            },
+            "--flexible" => {
+                config = config.flexible(true);
+            },
+            "--include-clients" => {
+                config = config.client_filter(Some(ClientFilter::Include(parse_clients(remaining.next(), "--include-clients")?)));
+            },
+            "--exclude-clients" => {
+                config = config.client_filter(Some(ClientFilter::Exclude(parse_clients(remaining.next(), "--exclude-clients")?)));
+            },
+            "--coalesce-deposits" => {
+                config = config.coalesce_deposits(true);
+            },
+            "--no-log" => {
+                config = config.no_log(true);
+            },
+            "--auto" => {
+                config = config.auto_detect(true);
+            },
+            "--strict-schema" => {
+                config = config.strict_schema(true);
+            },
+            "--no-negative" => {
+                no_negative = true;
+            },
+            "--strict" => {
+                strict = true;
+            },
+            "--acks" => {
+                acks = true;
+            },
+            "--zip" => {
+                if !cfg!(feature = "zip") {
+                    return Err("--zip requires the zip feature".to_string())
+                }
+                if file_path.is_some() {
+                    return Err("--zip replaces the file argument".to_string())
+                }
+                file_path = Some(remaining.next().ok_or("--zip requires the path to an archive")?.clone());
+                zip = true;
+            },
+            "--format" => {
+                match remaining.next().map(|format| format.as_str()) {
+                    Some("csv") => {parquet_path = None},
+                    Some("parquet") => {
+                        if !cfg!(feature = "parquet") {
+                            return Err("--format parquet requires the parquet feature".to_string())
+                        }
+                        parquet_path = Some(remaining.next().ok_or("--format parquet requires an output path")?.clone());
+                    },
+                    _ => {return Err("--format requires csv or parquet <path>".to_string())}
+                }
+            },
+            "--tx-count" => {
+                output.tx_count = true;
+            },
+            "--locked-only" => {
+                output.locked_only = true;
+            },
+            "--locked-by-tx" => {
+                output.locked_by_tx = true;
+            },
+            "--negative-total" => {
+                let selection = remaining.next().ok_or("--negative-total requires a value")?;
+                output.negative_total = selection.parse::<NegativeTotalOutput>()?;
+            },
+            "--delimiter" => {
+                output.delimiter = parse_single_byte(remaining.next(), "--delimiter")?;
+            },
+            "--decimal-sep" => {
+                output.decimal_separator = parse_single_byte(remaining.next(), "--decimal-sep")? as char;
+            },
+            "--quote-style" => {
+                let selection = remaining.next().ok_or("--quote-style requires a value")?;
+                output.quote_style = selection.parse::<QuoteStyle>()?;
+            },
+            "--fixed-scale" => {
+                let scale = remaining.next().ok_or("--fixed-scale requires a value")?;
+                output.fixed_scale = Some(scale.parse::<u32>().map_err(|_| "--fixed-scale requires a number of decimal places")?);
+            },
+            "--columns" => {
+                let selection = remaining.next().ok_or("--columns requires a value")?;
+                let columns = selection.split(',').map(|name| name.trim().parse::<Column>()).collect::<Result<Vec<Column>, String>>()?;
+                output.column_order = Some(columns);
+            },
+            other if other.starts_with("--") => {
+                return Err(format!("unknown option {}", other))
+            },
+            _ => {
+                if file_path.is_some() {
+                    return Err(format!("unexpected argument {}", arg))
+                }
+                file_path = Some(arg.clone());
+            }
+        }
+    }
+    let file_path = file_path.ok_or("usage: transactions [process|validate|stats|verify <claimed output>] [options] <file>")?;
+    if zip && (acks || matches!(command, Command::Verify(_))) {
+        return Err("--zip can not be used with --acks or verify".to_string())
+    }
+    // the Parquet file has fixed columns and types so none of the CSV output options would be applied
+    if parquet_path.is_some() && output != OutputOptions::default() {
+        return Err("--format parquet can not be used with the CSV output options".to_string())
+    }
+    return Ok(CliArgs{command, file_path, config: config.build(), no_negative, strict, acks, zip, parquet_path, output})
+}
+
 
-    let mut reader = csv::Reader::from_path(file_path).unwrap();
-    let mut account_map = AccountMap::new();
+/// Processes the transactions file and writes the result of the subcommand to stdout.
+/// With the interrupt feature a SIGINT stops reading and the subcommand is run on the accounts processed so far.
+///
+/// # Arguments
+/// * args (&CliArgs): the options passed on the command line
+///
+/// # Returns
+/// * (Result<(), EngineError>): an error if processing stopped
+fn run(args: &CliArgs) -> Result<(), EngineError> {
+    let file = File::open(&args.file_path)?;
+    if let Command::Verify(claimed_path) = &args.command {
+        let result = verify_with_config(file, File::open(claimed_path)?, &args.config, &args.output);
+        if result != VerifyResult::Matches {
+            return Err(EngineError::Unverified(result))
+        }
+        println!("{}", result);
+        return Ok(())
+    }
+    #[cfg(feature = "zip")]
+    if args.zip {
+        let account_map = archive::process_zip(file, &args.config)?;
+        return run_command(args, account_map)
+    }
+    if args.acks {
+        let account_map = process_transactions_with_acks(file, &args.config, io::stderr())?;
+        return run_command(args, account_map)
+    }
 
-    for result in reader.deserialize() {
-        let raw_transaction: TransactionSchema = result.unwrap();
-        let transaction = raw_transaction.convert_to_transaction();
-        account_map = log_transaction(Some(account_map), transaction);
+    #[cfg(feature = "interrupt")]
+    {
+        let interrupted = interrupt::install_handler()?;
+        let account_map = process_transactions_until(file, &args.config, &interrupted)?;
+        if interrupted.load(Ordering::SeqCst) {
+            eprintln!("interrupted after {} transactions, using the accounts processed so far", account_map.accepted_count + account_map.total_error_transaction_log.len());
+        }
+        return run_command(args, account_map)
+    }
+    #[cfg(not(feature = "interrupt"))]
+    {
+        let account_map = process_transactions(file, &args.config)?;
+        return run_command(args, account_map)
+    }
+}
+
+
+/// Writes the result of the subcommand for the processed accounts to stdout, and any skipped rows and warnings to stderr.
+///
+/// # Arguments
+/// * args (&CliArgs): the options passed on the command line
+/// * account_map (AccountMap): the accounts after processing
+///
+/// # Returns
+/// * (Result<(), EngineError>): an error if the accounts break a check or could not be written
+fn run_command(args: &CliArgs, account_map: AccountMap) -> Result<(), EngineError> {
+    for (line, message) in &account_map.skipped_rows {
+        eprintln!("skipping line {}: {}", line, message);
+    }
+    for (transaction, warning) in &account_map.total_warning_log {
+        eprintln!("warning: tx {} for client {}: {}", transaction.tx, transaction.client, warning);
     }
+    if args.no_negative {
+        check_no_negative(&account_map)?;
+    }
+    if args.strict {
+        account_map.assert_invariants().map_err(EngineError::InvariantViolations)?;
+    }
+
+    match args.command {
+        Command::Process => {
+            #[cfg(feature = "parquet")]
+            if let Some(path) = &args.parquet_path {
+                return columnar::write_parquet(&account_map, File::create(path)?)
+            }
+            return write_accounts(account_map, io::stdout(), &args.output)
+        },
+        Command::Validate => {
+            return write_validation_report(&account_map, io::stdout())
+        },
+        Command::Stats => {
+            println!("{}", EngineStats::from_map(&account_map).to_json());
+            return Ok(())
+        },
+        Command::Verify(_) => {
+            // verify re-runs the engine itself before any accounts are processed
+            unreachable!("verify is handled before processing")
+        }
+    }
+}
+
+
+fn main() {
+
+    let args: Vec<String> = env::args().collect();
 
-    let buffer = account_map.accounts.into_iter().map(|x|{AccountSchema::convert_from_account(x.1)}).collect::<Vec<AccountSchema>>();
-    
-    let mut wtr = csv::Writer::from_writer(io::stdout());
+    let cli_args = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
 
-    for account in buffer {
-        wtr.serialize(account);
+    if let Err(error) = run(&cli_args) {
+        eprintln!("{}", error);
+        process::exit(1);
     }
 }
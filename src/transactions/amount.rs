@@ -0,0 +1,284 @@
+/// The number of decimal places amounts are accepted at unless configured otherwise.
+pub const DEFAULT_SCALE: u32 = 4;
+
+/// Half of the smallest unit at the default scale, float values closer than this are treated as equal.
+pub const DEFAULT_EPSILON: f32 = 0.00005;
+
+/// The type amounts and balances are held in during processing.
+pub type Money = f32;
+
+/// The largest number of units an f32 holds exactly, 2^24, beyond it not every unit can be represented.
+const F32_EXACT_UNITS: f64 = 16_777_216.0;
+
+
+/// Checks if an amount is too large for an f32 to hold every unit at a scale, so it may already have been rounded.
+///
+/// # Arguments
+/// * amount (f32): the amount as parsed
+/// * scale (u32): the number of decimal places amounts are kept at
+///
+/// # Returns
+/// * (bool): true if the amount is at or beyond the exactly representable range
+pub fn exceeds_exact_range(amount: f32, scale: u32) -> bool {
+    // 2^24 itself is exact but the unit after it is not, so an amount there may be one that was rounded down
+    return (amount as f64).abs() * 10_f64.powi(scale as i32) >= F32_EXACT_UNITS
+}
+
+
+/// This struct is responsible for describing how amounts are written by feeds that format them for people, such as spreadsheets.
+///
+/// # Attributes
+/// * currency_symbol (Option<String>): a symbol written before or after the amount, for example "$"
+/// * thousands_separator (Option<char>): the character grouping the digits of the whole part, for example ','
+/// * decimal_separator (char): the character separating the whole and fractional parts
+#[derive(Debug, PartialEq, Clone)]
+pub struct AmountFormat {
+    pub currency_symbol: Option<String>,
+    pub thousands_separator: Option<char>,
+    pub decimal_separator: char
+}
+
+impl Default for AmountFormat {
+
+    fn default() -> Self {
+        return AmountFormat{currency_symbol: None, thousands_separator: None, decimal_separator: '.'}
+    }
+}
+
+impl AmountFormat {
+
+    /// Rewrites an amount in this format as the plain decimal FixedPoint::parse expects.
+    ///
+    /// # Arguments
+    /// * raw (&str): the amount as it appears in the input, for example "$1,000.50"
+    ///
+    /// # Returns
+    /// * (String): the amount without the symbol or thousands separators and with a '.' decimal point, for example "1000.50"
+    pub fn sanitize(&self, raw: &str) -> String {
+        let mut cleaned = raw.trim().to_string();
+        if let Some(symbol) = self.currency_symbol.as_deref().filter(|symbol| !symbol.is_empty()) {
+            cleaned = cleaned.replace(symbol, "");
+        }
+        if let Some(separator) = self.thousands_separator {
+            cleaned = cleaned.replace(separator, "");
+        }
+        if self.decimal_separator != '.' {
+            cleaned = cleaned.replace(self.decimal_separator, ".");
+        }
+        return cleaned.trim().to_string()
+    }
+}
+
+
+/// This struct is responsible for holding an amount as a whole number of units at a decimal scale.
+///
+/// # Attributes
+/// * units (i64): the amount multiplied by 10^scale
+/// * scale (u32): the number of decimal places the units represent
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FixedPoint {
+    pub units: i64,
+    pub scale: u32
+}
+
+impl FixedPoint {
+
+    /// Parses a decimal string directly into fixed-point units without passing through a float.
+    ///
+    /// # Arguments
+    /// * raw (&str): the amount as it appears in the input, for example "1.5"
+    /// * scale (u32): the number of decimal places allowed
+    ///
+    /// # Returns
+    /// * (Result<FixedPoint, String>): the parsed amount, or an error if it is malformed or has more decimal places than the scale
+    pub fn parse(raw: &str, scale: u32) -> Result<FixedPoint, String> {
+        let trimmed = raw.trim();
+        let (negative, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed))
+        };
+        let expanded: String;
+        let unsigned = match unsigned.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => {
+                expanded = expand_exponent(mantissa, exponent).ok_or(format!("amount '{}' is not a number", raw))?;
+                expanded.as_str()
+            },
+            None => unsigned
+        };
+        let (whole, fraction) = match unsigned.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (unsigned, "")
+        };
+
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(format!("amount '{}' is not a number", raw))
+        }
+        if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(format!("amount '{}' is not a number", raw))
+        }
+
+        // trailing zeros do not change the value so they do not count against the scale
+        let significant_fraction = fraction.trim_end_matches('0');
+        if significant_fraction.len() > scale as usize {
+            return Err(format!("amount '{}' cannot be represented exactly with {} decimal places", raw, scale))
+        }
+
+        let padded_fraction = format!("{:0<width$}", significant_fraction, width = scale as usize);
+        let digits = format!("{}{}", whole, padded_fraction);
+        let mut units: i64 = match digits.trim_start_matches('0') {
+            "" => 0,
+            significant => significant.parse::<i64>().map_err(|_| format!("amount '{}' is too large", raw))?
+        };
+        if negative {
+            units = -units;
+        }
+        return Ok(FixedPoint{units, scale})
+    }
+
+    /// Converts the fixed-point amount into the float used by the engine.
+    ///
+    /// # Returns
+    /// * (f32): the closest f32 to the exact decimal value
+    pub fn to_f32(&self) -> f32 {
+        return (self.units as f64 / 10_f64.powi(self.scale as i32)) as f32
+    }
+
+    /// Converts a float into the closest whole number of units at a scale, the inverse of to_f32 while the float is within half a unit of the decimal.
+    ///
+    /// # Arguments
+    /// * amount (f32): the amount to convert
+    /// * scale (u32): the number of decimal places the units represent
+    ///
+    /// # Returns
+    /// * (FixedPoint): the amount rounded to the nearest unit
+    pub fn from_f32(amount: f32, scale: u32) -> FixedPoint {
+        // the scaling is done in f64 so the f32 is not rounded again before it is counted in units
+        return FixedPoint{units: (amount as f64 * 10_f64.powi(scale as i32)).round() as i64, scale}
+    }
+
+    /// Checks if the amount has more units than an f32 holds exactly, so to_f32 may round it.
+    ///
+    /// # Returns
+    /// * (bool): true if the units are at or beyond 2^24 either side of zero
+    pub fn exceeds_exact_range(&self) -> bool {
+        return self.units.unsigned_abs() as f64 >= F32_EXACT_UNITS
+    }
+}
+
+
+/// The largest exponent accepted in scientific notation, beyond it no amount fits in the units anyway.
+const MAX_EXPONENT: i64 = 38;
+
+
+/// Rewrites an amount in scientific notation as a plain decimal by moving the decimal point, so 1.5e-1 becomes 0.15.
+///
+/// # Arguments
+/// * mantissa (&str): the digits before the e without a sign, for example "1.5"
+/// * exponent (&str): the power of ten after the e, optionally signed
+///
+/// # Returns
+/// * (Option<String>): the plain decimal, None if either part is malformed or the exponent is out of range
+fn expand_exponent(mantissa: &str, exponent: &str) -> Option<String> {
+    let exponent = exponent.parse::<i64>().ok().filter(|exponent| exponent.abs() <= MAX_EXPONENT)?;
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None
+    }
+    let digits = format!("{}{}", whole, fraction);
+    let point = whole.len() as i64 + exponent;
+
+    if point <= 0 {
+        return Some(format!("0.{}{}", "0".repeat(-point as usize), digits))
+    }
+    let point = point as usize;
+    if point >= digits.len() {
+        return Some(format!("{}{}", digits, "0".repeat(point - digits.len())))
+    }
+    return Some(format!("{}.{}", &digits[..point], &digits[point..]))
+}
+
+
+#[cfg(test)]
+mod amount_tests {
+
+    use super::{exceeds_exact_range, AmountFormat, FixedPoint, DEFAULT_SCALE};
+
+    #[test]
+    fn test_parse_one_tenth() {
+        let amount = FixedPoint::parse("0.1", DEFAULT_SCALE).unwrap();
+        assert_eq!(FixedPoint{units: 1000, scale: 4}, amount);
+    }
+
+    #[test]
+    fn test_parse_smallest_unit() {
+        let amount = FixedPoint::parse("0.0001", DEFAULT_SCALE).unwrap();
+        assert_eq!(FixedPoint{units: 1, scale: 4}, amount);
+    }
+
+    #[test]
+    fn test_parse_unrepresentable() {
+        let amount = FixedPoint::parse("0.00001", DEFAULT_SCALE);
+        assert!(amount.is_err());
+    }
+
+    #[test]
+    fn test_parse_trailing_zeros_and_sign() {
+        assert_eq!(FixedPoint{units: 15000, scale: 4}, FixedPoint::parse(" 1.500000 ", DEFAULT_SCALE).unwrap());
+        assert_eq!(FixedPoint{units: -20000, scale: 4}, FixedPoint::parse("-2", DEFAULT_SCALE).unwrap());
+        assert!(FixedPoint::parse("abc", DEFAULT_SCALE).is_err());
+        assert!(FixedPoint::parse(".", DEFAULT_SCALE).is_err());
+    }
+
+    #[test]
+    fn test_parse_scientific_notation() {
+        assert_eq!(FixedPoint{units: 1_000_000, scale: 4}, FixedPoint::parse("1e2", DEFAULT_SCALE).unwrap());
+        assert_eq!(FixedPoint{units: 1500, scale: 4}, FixedPoint::parse("1.5e-1", DEFAULT_SCALE).unwrap());
+        assert_eq!(FixedPoint{units: -250_000, scale: 4}, FixedPoint::parse("-2.5E+1", DEFAULT_SCALE).unwrap());
+        assert_eq!(FixedPoint{units: 1, scale: 4}, FixedPoint::parse("1e-4", DEFAULT_SCALE).unwrap());
+        assert_eq!(15.0, FixedPoint::parse("1.5e1", DEFAULT_SCALE).unwrap().to_f32());
+    }
+
+    #[test]
+    fn test_parse_scientific_notation_errors() {
+        assert_eq!(Err("amount '1.5e-5' cannot be represented exactly with 4 decimal places".to_string()), FixedPoint::parse("1.5e-5", DEFAULT_SCALE));
+        assert_eq!(Err("amount '1e' is not a number".to_string()), FixedPoint::parse("1e", DEFAULT_SCALE));
+        assert_eq!(Err("amount 'e2' is not a number".to_string()), FixedPoint::parse("e2", DEFAULT_SCALE));
+        assert_eq!(Err("amount '1e2e3' is not a number".to_string()), FixedPoint::parse("1e2e3", DEFAULT_SCALE));
+        assert_eq!(Err("amount '1e400' is not a number".to_string()), FixedPoint::parse("1e400", DEFAULT_SCALE));
+        assert!(FixedPoint::parse("9e30", DEFAULT_SCALE).is_err());
+    }
+
+    #[test]
+    fn test_from_f32() {
+        assert_eq!(FixedPoint{units: 1000, scale: 4}, FixedPoint::from_f32(0.1, DEFAULT_SCALE));
+        assert_eq!(FixedPoint{units: -1_234_500, scale: 4}, FixedPoint::from_f32(-123.45, DEFAULT_SCALE));
+        assert_eq!(FixedPoint{units: 150, scale: 2}, FixedPoint::from_f32(FixedPoint{units: 150, scale: 2}.to_f32(), 2));
+    }
+
+    #[test]
+    fn test_sanitize_spreadsheet_amounts() {
+        let dollars = AmountFormat{currency_symbol: Some("$".to_string()), thousands_separator: Some(','), decimal_separator: '.'};
+        assert_eq!("1000.50", dollars.sanitize("$1,000.50"));
+        assert_eq!("-2000", dollars.sanitize(" -$2,000 "));
+        assert_eq!(FixedPoint{units: 10_005_000, scale: 4}, FixedPoint::parse(&dollars.sanitize("$1,000.50"), DEFAULT_SCALE).unwrap());
+
+        let european = AmountFormat{currency_symbol: Some("EUR".to_string()), thousands_separator: Some(' '), decimal_separator: ','};
+        assert_eq!("1000.50", european.sanitize("1 000,50"));
+        assert_eq!("1000.50", european.sanitize("1 000,50 EUR"));
+
+        assert_eq!("1,000.50", AmountFormat::default().sanitize("1,000.50"));
+    }
+
+    #[test]
+    fn test_exceeds_exact_range() {
+        assert!(!exceeds_exact_range(16777215.0, 0));
+        assert!(exceeds_exact_range(16777216.0, 0));
+        assert!(exceeds_exact_range(-16777217.0, 0));
+        assert!(!exceeds_exact_range(1677.7214, 4));
+        assert!(exceeds_exact_range(1677.7217, 4));
+
+        // the parsed units are counted directly so the scale does not need a float
+        assert!(!FixedPoint{units: 16_777_215, scale: 2}.exceeds_exact_range());
+        assert!(FixedPoint{units: -16_777_216, scale: 8}.exceeds_exact_range());
+    }
+}
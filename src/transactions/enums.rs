@@ -9,13 +9,15 @@ use std::cmp::PartialEq;
 /// * DISPUTE: a client's claim that a transaction was erroneous and should be reversed
 /// * RESOLVE: a resolution to a dispute, releasing the associated held funds
 /// * CHARGEBACK: the final state of a dispute and represents the client reversing a transaction
+/// * FREEZE: an administrative lock on the account independent of any dispute
 #[derive(Debug, PartialEq, Clone)]
 pub enum TransactionType {
     DEPOSIT,
     WITHDRAWAL,
     DISPUTE,
     RESOLVE,
-    CHARGEBACK
+    CHARGEBACK,
+    FREEZE
 }
 
 impl TransactionType {
@@ -24,14 +26,30 @@ impl TransactionType {
     /// 
     /// # Arguments 
     /// * selection (String): the selection for the enum to be created on
-    pub fn new(selection: String) -> TransactionType {
+    /// 
+    /// # Returns 
+    /// * (Result<TransactionType, String>): the transaction type, or an error if the selection is not supported
+    pub fn new(selection: String) -> Result<TransactionType, String> {
         match selection.as_str() {
-            "deposit" => {return TransactionType::DEPOSIT},
-            "withdrawal" => {return TransactionType::WITHDRAWAL},
-            "dispute" => {return TransactionType::DISPUTE},
-            "resolve" => {return TransactionType::RESOLVE},
-            "chargeback" => {return TransactionType::CHARGEBACK},
-            _ => {panic!("selection not supported")},
+            "deposit" => {return Ok(TransactionType::DEPOSIT)},
+            "withdrawal" => {return Ok(TransactionType::WITHDRAWAL)},
+            "dispute" => {return Ok(TransactionType::DISPUTE)},
+            "resolve" => {return Ok(TransactionType::RESOLVE)},
+            "chargeback" => {return Ok(TransactionType::CHARGEBACK)},
+            "freeze" => {return Ok(TransactionType::FREEZE)},
+            _ => {return Err(format!("transaction type '{}' is not supported", selection))},
         }
     }
 }
+
+
+/// This enum is responsible for defining why an account was locked. 
+/// 
+/// # Attributes 
+/// * Chargeback: a chargeback reversed a disputed transaction
+/// * Administrative: the account was frozen by a freeze transaction
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LockReason {
+    Chargeback,
+    Administrative
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+use super::enums::TransactionType;
+
+
+/// This enum is responsible for defining why a transaction was rejected by an account.
+///
+/// # Attributes
+/// * AccountLocked: the account is locked so no more transactions can go through it
+/// * AlreadyLocked: a freeze was made on an account that is already locked
+/// * MissingAmount: a deposit or withdrawal was made without an amount
+/// * AmountNotApplicable: an amount was asked for on a type of transaction that does not carry one
+/// * ZeroAmount: a deposit or withdrawal was made for exactly zero
+/// * InsufficientFunds: a withdrawal was larger than the available funds
+/// * InsufficientHeldFunds: a chargeback was larger than the held funds
+/// * DisputeNotFound: a chargeback was made for a transaction that is not under dispute
+/// * BalanceCapExceeded: a deposit would push the total above the configured maximum
+/// * AlreadyResolved: a dispute was made on a transaction that has been resolved when that is not allowed
+/// * AlreadyDisputed: a dispute was made on a transaction that is already under dispute
+/// * TooManyOpenDisputes: a dispute was made while the client already has the most open disputes allowed
+/// * DuplicateTxId: a deposit or withdrawal reused the ID of an earlier one
+/// * TransactionNotFound: the deposit or withdrawal referred to is not in the account's log
+/// * NegativeDeposit: a deposit was made for a negative amount
+/// * AmountMismatch: a dispute, resolve or chargeback carried an amount that disagrees with the amount it refers to
+/// * LogDisabled: a dispute, resolve or chargeback was made while the transaction log is turned off
+/// * DisputesDisabled: a dispute, resolve or chargeback was made while deposits are coalesced
+/// * AmountTooLarge: a deposit or withdrawal, or the balance a deposit leaves, is beyond the range f32 holds exactly at the scale of the account
+#[derive(Debug, PartialEq, Clone)]
+pub enum TransactionError {
+    AccountLocked,
+    AlreadyLocked,
+    MissingAmount(TransactionType),
+    AmountNotApplicable(TransactionType),
+    ZeroAmount,
+    InsufficientFunds,
+    InsufficientHeldFunds,
+    DisputeNotFound,
+    BalanceCapExceeded,
+    AlreadyResolved,
+    AlreadyDisputed,
+    TooManyOpenDisputes,
+    DuplicateTxId,
+    TransactionNotFound,
+    NegativeDeposit,
+    AmountMismatch,
+    LogDisabled,
+    DisputesDisabled,
+    AmountTooLarge
+}
+
+impl fmt::Display for TransactionError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::AccountLocked => {return write!(f, "account is locked")},
+            TransactionError::AlreadyLocked => {return write!(f, "account is already locked")},
+            TransactionError::MissingAmount(transaction_type) => {return write!(f, "{:?} has no amount", transaction_type)},
+            TransactionError::AmountNotApplicable(transaction_type) => {return write!(f, "{:?} does not carry an amount", transaction_type)},
+            TransactionError::ZeroAmount => {return write!(f, "amount is zero")},
+            TransactionError::InsufficientFunds => {return write!(f, "not enough funds for withdrawal")},
+            TransactionError::InsufficientHeldFunds => {return write!(f, "not enough held funds for the chargeback")},
+            TransactionError::DisputeNotFound => {return write!(f, "no dispute found for the chargeback")},
+            TransactionError::BalanceCapExceeded => {return write!(f, "balance cap exceeded")},
+            TransactionError::AlreadyResolved => {return write!(f, "transaction already resolved")},
+            TransactionError::AlreadyDisputed => {return write!(f, "transaction already disputed")},
+            TransactionError::TooManyOpenDisputes => {return write!(f, "too many open disputes")},
+            TransactionError::DuplicateTxId => {return write!(f, "transaction ID already used")},
+            TransactionError::TransactionNotFound => {return write!(f, "transaction not found")},
+            TransactionError::NegativeDeposit => {return write!(f, "deposit amount is negative")},
+            TransactionError::AmountMismatch => {return write!(f, "amount does not match the amount it refers to")},
+            TransactionError::LogDisabled => {return write!(f, "log disabled, disputes can not be looked up")},
+            TransactionError::DisputesDisabled => {return write!(f, "disputes are disabled while deposits are coalesced")},
+            TransactionError::AmountTooLarge => {return write!(f, "amount is too large to be held exactly")}
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+
+/// This struct is responsible for wrapping the ID of a client so it can not be passed where a transaction ID is expected.
+///
+/// # Attributes
+/// * 0 (u32): the raw ID
+///
+/// A transaction ID can not be used in its place:
+///
+/// ```compile_fail
+/// use transactions::transactions::ids::{ClientId, TxId};
+///
+/// fn lookup(client: ClientId) -> u32 {
+///     return client.0
+/// }
+/// lookup(TxId(1));
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub struct ClientId(pub u32);
+
+/// This struct is responsible for wrapping the ID of a transaction so it can not be passed where a client ID is expected.
+///
+/// # Attributes
+/// * 0 (u32): the raw ID
+///
+/// A client ID can not be used in its place:
+///
+/// ```compile_fail
+/// use transactions::transactions::ids::{ClientId, TxId};
+///
+/// fn lookup(tx: TxId) -> u32 {
+///     return tx.0
+/// }
+/// lookup(ClientId(1));
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub struct TxId(pub u32);
+
+impl From<u32> for ClientId {
+
+    fn from(id: u32) -> Self {
+        return ClientId(id)
+    }
+}
+
+impl From<u32> for TxId {
+
+    fn from(id: u32) -> Self {
+        return TxId(id)
+    }
+}
+
+impl TryFrom<i32> for ClientId {
+
+    type Error = String;
+
+    fn try_from(id: i32) -> Result<Self, Self::Error> {
+        return u32::try_from(id).map(ClientId).map_err(|_| format!("client ID {} is negative", id))
+    }
+}
+
+impl TryFrom<i32> for TxId {
+
+    type Error = String;
+
+    fn try_from(id: i32) -> Result<Self, Self::Error> {
+        return u32::try_from(id).map(TxId).map_err(|_| format!("transaction ID {} is negative", id))
+    }
+}
+
+impl fmt::Display for ClientId {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for TxId {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.0)
+    }
+}
+
+
+#[cfg(test)]
+mod ids_tests {
+
+    use super::{ClientId, TxId};
+
+    #[test]
+    fn test_from_and_display() {
+        let client = ClientId::from(7);
+        let tx: TxId = 7.into();
+
+        assert_eq!("7", client.to_string());
+        assert_eq!("7", tx.to_string());
+        assert_eq!(client.0, tx.0);
+    }
+
+    #[test]
+    fn test_try_from_i32() {
+        assert_eq!(Ok(ClientId(3)), ClientId::try_from(3));
+        assert_eq!(Ok(TxId(0)), TxId::try_from(0));
+        assert_eq!(Err("client ID -1 is negative".to_string()), ClientId::try_from(-1));
+        assert_eq!(Err("transaction ID -2 is negative".to_string()), TxId::try_from(-2));
+    }
+}
@@ -1,2 +1,6 @@
+pub mod amount;
 pub mod enums;
+pub mod errors;
+pub mod ids;
 pub mod transaction;
+pub mod warnings;
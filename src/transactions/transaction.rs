@@ -1,17 +1,81 @@
+use super::amount::{FixedPoint, Money};
 use super::enums::TransactionType;
+use super::errors::TransactionError;
+use super::ids::{ClientId, TxId};
 
 
 /// This struct is responsible for housing data for inividual transactions.
 /// 
 /// # Attributes 
 /// * transaction_type (TransactionType): the type of transaction being made 
-/// * client (i32): the ID of the account making the transaction 
-/// * tx (i32): the ID of the transaction 
+/// * client (ClientId): the ID of the account making the transaction 
+/// * tx (TxId): the ID of the transaction 
 /// * amount (Option<f32>): the amount involved for the transaction
+/// * exact_amount (Option<FixedPoint>): the amount in whole units at the scale it was parsed at, None if it was not read from text
 #[derive(Debug, Clone)]
 pub struct Transaction {
     pub transaction_type: TransactionType,
-    pub client: i32,
-    pub tx: i32,
-    pub amount: Option<f32>
+    pub client: ClientId,
+    pub tx: TxId,
+    pub amount: Option<f32>,
+    pub exact_amount: Option<FixedPoint>
+}
+
+impl Transaction {
+
+    /// The constructor for a Transaction that was not read from text, so there is no exact amount. 
+    /// 
+    /// # Arguments 
+    /// * transaction_type (TransactionType): the type of transaction being made 
+    /// * client (ClientId): the ID of the account making the transaction 
+    /// * tx (TxId): the ID of the transaction 
+    /// * amount (Option<f32>): the amount involved for the transaction
+    /// 
+    /// # Returns 
+    /// * (Transaction): the newly constructed transaction
+    pub fn new(transaction_type: TransactionType, client: ClientId, tx: TxId, amount: Option<f32>) -> Transaction {
+        return Transaction{transaction_type, client, tx, amount, exact_amount: None}
+    }
+
+    /// Gets the amount of a deposit or withdrawal. 
+    /// 
+    /// # Returns 
+    /// * (Result<Money, TransactionError>): the amount, or an error if it is missing or the type does not carry one
+    pub fn require_amount(&self) -> Result<Money, TransactionError> {
+        match self.transaction_type {
+            TransactionType::DEPOSIT | TransactionType::WITHDRAWAL => {
+                return self.amount.ok_or(TransactionError::MissingAmount(self.transaction_type.clone()))
+            },
+            _ => {
+                return Err(TransactionError::AmountNotApplicable(self.transaction_type.clone()))
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod transaction_tests {
+
+    use super::{Transaction, TransactionError};
+    use super::super::enums::TransactionType::{DEPOSIT, DISPUTE, WITHDRAWAL};
+    use super::super::ids::{ClientId, TxId};
+
+    #[test]
+    fn test_require_amount_present() {
+        let deposit =    Transaction::new(DEPOSIT,    ClientId(1), TxId(1), Some(2.5));
+        let withdrawal = Transaction::new(WITHDRAWAL, ClientId(1), TxId(2), Some(1.0));
+
+        assert_eq!(Ok(2.5), deposit.require_amount());
+        assert_eq!(Ok(1.0), withdrawal.require_amount());
+    }
+
+    #[test]
+    fn test_require_amount_missing() {
+        let deposit = Transaction::new(DEPOSIT, ClientId(1), TxId(1), None);
+        let dispute = Transaction::new(DISPUTE, ClientId(1), TxId(1), Some(2.5));
+
+        assert_eq!(Err(TransactionError::MissingAmount(DEPOSIT)), deposit.require_amount());
+        assert_eq!(Err(TransactionError::AmountNotApplicable(DISPUTE)), dispute.require_amount());
+    }
 }
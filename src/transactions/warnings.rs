@@ -0,0 +1,26 @@
+use std::fmt;
+
+
+/// This enum is responsible for defining why a transaction that was not rejected still needs looking at.
+///
+/// # Attributes
+/// * DuplicateDropped: a deposit or withdrawal reused the ID of an earlier one and was dropped so the first one is kept
+/// * AmountMismatch: a dispute, resolve or chargeback carried an amount that disagrees with the amount it refers to
+/// * AmountBeyondExactRange: a deposit or withdrawal, or the balances it leaves, are beyond the range f32 holds exactly at the scale of the account
+#[derive(Debug, PartialEq, Clone)]
+pub enum TransactionWarning {
+    DuplicateDropped,
+    AmountMismatch { declared: f32, expected: f32 },
+    AmountBeyondExactRange
+}
+
+impl fmt::Display for TransactionWarning {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionWarning::DuplicateDropped => {return write!(f, "duplicate transaction ID dropped, the first one is kept")},
+            TransactionWarning::AmountMismatch { declared, expected } => {return write!(f, "carries {} but {} is expected", declared, expected)},
+            TransactionWarning::AmountBeyondExactRange => {return write!(f, "amount is beyond the range held exactly")}
+        }
+    }
+}
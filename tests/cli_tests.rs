@@ -0,0 +1,128 @@
+use std::process::{Command, Output};
+
+
+/// Runs the compiled binary with the given arguments.
+///
+/// # Arguments
+/// * args (&[&str]): the arguments passed to the binary
+///
+/// # Returns
+/// * (Output): the captured output of the run
+fn run_binary(args: &[&str]) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_transactions")).args(args).output().unwrap()
+}
+
+
+#[test]
+fn test_bad_row_continue() {
+    let output = run_binary(&["--on-error", "continue", "tests/fixtures/bad_row.csv"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("1,2.0,0.0,2.0,false"));
+    assert!(stdout.contains("2,3.0,0.0,3.0,false"));
+    assert!(stderr.contains("line 3"));
+}
+
+#[test]
+fn test_bad_row_continue_is_default() {
+    let output = run_binary(&["tests/fixtures/bad_row.csv"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_bad_row_fail() {
+    let output = run_binary(&["--on-error", "fail", "tests/fixtures/bad_row.csv"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(stdout.is_empty());
+    assert!(stderr.contains("line 3"));
+}
+
+#[test]
+fn test_negative_allowed_by_default() {
+    let output = run_binary(&["tests/fixtures/negative_chargeback.csv"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("1,-8.0,0.0,-8.0,true"));
+}
+
+#[test]
+fn test_no_negative() {
+    let output = run_binary(&["--no-negative", "tests/fixtures/negative_chargeback.csv"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("clients with negative available funds: 1"));
+}
+
+#[test]
+fn test_process_subcommand() {
+    let output = run_binary(&["process", "tests/fixtures/mixed.csv"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.starts_with("client,available,held,total,locked\n"));
+    assert!(stdout.contains("1,2.0,0.0,2.0,false"));
+    assert!(stdout.contains("2,1.5,0.0,1.5,false"));
+}
+
+#[test]
+fn test_validate_subcommand() {
+    let output = run_binary(&["validate", "tests/fixtures/mixed.csv"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!("accepted: 2\nrejected: 1\nrejected WITHDRAWAL tx 2 for client 1\n", stdout);
+}
+
+#[test]
+fn test_stats_subcommand() {
+    let output = run_binary(&["stats", "tests/fixtures/mixed.csv"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!("{\"accepted_transactions\":2,\"rejected_transactions\":1,\"accounts\":2,\"locked_accounts\":0}\n", stdout);
+}
+
+#[test]
+fn test_unknown_option() {
+    let output = run_binary(&["process", "--bogus", "tests/fixtures/mixed.csv"]);
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn test_progress_interval() {
+    let output = run_binary(&["--progress-interval", "5", "tests/fixtures/ten_rows.csv"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!("processed 5 rows\nprocessed 10 rows\n", stderr);
+    assert_eq!("client,available,held,total,locked\n1,10.0,0.0,10.0,false\n", stdout);
+}
+
+#[test]
+fn test_auto_detect() {
+    let output = run_binary(&["--auto", "tests/fixtures/semicolon_headerless.csv"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!("client,available,held,total,locked\n1,2.0,0.0,2.0,false\n2,1.0,0.0,1.0,false\n", stdout);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_parquet_rejects_csv_options() {
+    let path = std::env::temp_dir().join(format!("cli_accounts_{}.parquet", std::process::id()));
+    let output = run_binary(&["process", "--format", "parquet", path.to_str().unwrap(), "--tx-count", "tests/fixtures/mixed.csv"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(Some(2), output.status.code());
+    assert!(stderr.contains("--format parquet can not be used with the CSV output options"));
+    assert!(!path.exists());
+}
@@ -0,0 +1,50 @@
+use std::env;
+use std::fs;
+use std::fs::File;
+
+use transactions::engine::{process_transactions, write_accounts};
+use transactions::engine::config::{EngineConfig, OutputOptions};
+
+
+/// Processes a golden input file and compares the written accounts with its expected output.
+/// Running with GOLDEN_UPDATE=1 writes the current output over the expected file instead.
+///
+/// # Arguments
+/// * scenario (&str): the name of the files in tests/golden, the input is <scenario>.csv and the output <scenario>.expected.csv
+fn check_golden(scenario: &str) {
+    let input_path = format!("tests/golden/{}.csv", scenario);
+    let expected_path = format!("tests/golden/{}.expected.csv", scenario);
+
+    let account_map = process_transactions(File::open(&input_path).unwrap(), &EngineConfig::default()).unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    write_accounts(account_map, &mut output, &OutputOptions::default()).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    if env::var("GOLDEN_UPDATE").is_ok_and(|value| value == "1") {
+        fs::write(&expected_path, &output).unwrap();
+        return
+    }
+    let expected = fs::read_to_string(&expected_path).unwrap();
+    assert_eq!(expected, output, "output of {} does not match {}", input_path, expected_path);
+}
+
+
+#[test]
+fn test_golden_deposit_withdraw() {
+    check_golden("deposit_withdraw");
+}
+
+#[test]
+fn test_golden_dispute_hold() {
+    check_golden("dispute_hold");
+}
+
+#[test]
+fn test_golden_dispute_resolve() {
+    check_golden("dispute_resolve");
+}
+
+#[test]
+fn test_golden_dispute_chargeback() {
+    check_golden("dispute_chargeback");
+}